@@ -0,0 +1,363 @@
+// A full backup of the journal as json, and restoring a database from one
+use crate::bargs;
+use crate::bargs::BJournRunner;
+use crate::config;
+use crate::db;
+use crate::migrations;
+use serde_json::json;
+
+// The backup as json, the bullets use the same keys as list -o json so import can read it too
+pub fn to_json(backup: &db::Backup, config_file: Option<&str>) -> serde_json::Value {
+    let env: serde_json::Map<String, serde_json::Value> = backup
+        .env
+        .iter()
+        .map(|(key, value)| (key.clone(), json!(value)))
+        .collect();
+    let bullets: Vec<serde_json::Value> = backup
+        .bullets
+        .iter()
+        .map(|b| {
+            json!({
+                "quickid": b.quickid,
+                "bullet": b.text,
+                "date": b.list_date,
+                "added": b.added,
+                "updated": b.updated,
+                "deleted_at": b.deleted_at,
+                "kind": b.kind,
+                "status": b.status,
+            })
+        })
+        .collect();
+
+    json!({
+        "bjourn": env!("CARGO_PKG_VERSION"),
+        "schema_version": backup.schema_version,
+        "config": config_file,
+        "env": env,
+        "bullets": bullets,
+    })
+}
+
+fn backup_bullet(value: &serde_json::Value) -> Result<db::BackupBullet, String> {
+    let string = |key: &str| value.get(key).and_then(|v| v.as_str()).map(String::from);
+    let required = |key: &str| string(key).ok_or(format!("a bullet is missing {}", key));
+
+    let bullet = db::BackupBullet {
+        quickid: required("quickid")?,
+        added: required("added")?,
+        list_date: required("date")?,
+        text: required("bullet")?,
+        updated: string("updated"),
+        deleted_at: string("deleted_at"),
+        // backups from before kinds were added are open notes
+        kind: string("kind").unwrap_or(db::KINDS[0].to_string()),
+        status: string("status").unwrap_or(db::STATUSES[0].to_string()),
+    };
+    if !db::KINDS.contains(&bullet.kind.as_str()) {
+        return Err(format!("unknown kind {}", bullet.kind));
+    }
+    if !db::STATUSES.contains(&bullet.status.as_str()) {
+        return Err(format!("unknown status {}", bullet.status));
+    }
+
+    Ok(bullet)
+}
+
+// Reads a backup written by to_json, returns the config file text with it
+pub fn from_json(content: &str) -> Result<(db::Backup, Option<String>), String> {
+    let value: serde_json::Value = serde_json::from_str(content).map_err(|e| e.to_string())?;
+
+    let schema_version = value
+        .get("schema_version")
+        .and_then(|v| v.as_i64())
+        .ok_or("not a bjourn backup, there is no schema_version")?;
+    if schema_version > migrations::latest_version() {
+        return Err(format!(
+            "the backup schema version {} is newer than this version of bjourn supports ({}), please upgrade bjourn",
+            schema_version,
+            migrations::latest_version()
+        ));
+    }
+
+    let mut env = Vec::new();
+    if let Some(values) = value.get("env").and_then(|e| e.as_object()) {
+        for (key, value) in values {
+            match value.as_str() {
+                Some(v) => env.push((key.clone(), v.to_string())),
+                None => return Err(format!("the env value for {} is not a string", key)),
+            }
+        }
+    }
+
+    let bullets = match value.get("bullets") {
+        Some(serde_json::Value::Array(values)) => values
+            .iter()
+            .map(backup_bullet)
+            .collect::<Result<Vec<db::BackupBullet>, String>>()?,
+        _ => return Err("not a bjourn backup, there is no list of bullets".to_string()),
+    };
+
+    let config_file = value
+        .get("config")
+        .and_then(|c| c.as_str())
+        .map(String::from);
+
+    Ok((
+        db::Backup {
+            schema_version,
+            env,
+            bullets,
+        },
+        config_file,
+    ))
+}
+
+pub fn backup(_args: &bargs::BArgs) {
+    let backup = match db::backup() {
+        Ok(b) => b,
+        Err(e) => {
+            eprintln!("Error reading the database: {}", e);
+            std::process::exit(exitcode::IOERR);
+        }
+    };
+    let config_file = config::config_location().and_then(|p| std::fs::read_to_string(p).ok());
+
+    match serde_json::to_string_pretty(&to_json(&backup, config_file.as_deref())) {
+        Ok(out) => println!("{}", out),
+        Err(e) => {
+            eprintln!("Error writing the backup: {}", e);
+            std::process::exit(exitcode::SOFTWARE);
+        }
+    }
+}
+
+// Whether restore was given a backup file rather than a quickid, quickids and positions never
+// have a dot or a slash so a mistyped file name is still reported as a missing file
+pub fn is_backup_path(input: &str) -> bool {
+    input.contains('/')
+        || input.contains('\\')
+        || input.contains('.')
+        || std::path::Path::new(input).is_file()
+}
+
+// the config file is only written by a replace, or when there isn't one yet,
+// returns where it was written and whether an existing file was overwritten
+fn restore_config(
+    config_file: &str,
+    replace: bool,
+) -> Result<Option<(std::path::PathBuf, bool)>, String> {
+    let path = match config::config_location() {
+        Some(p) => p,
+        None => return Ok(None),
+    };
+    let exists = path.exists();
+    if exists && !replace {
+        return Ok(None);
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    }
+    std::fs::write(&path, config_file).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+    Ok(Some((path, exists)))
+}
+
+pub fn restore(args: &bargs::BArgs, path: &str) {
+    if args.has_flag("merge") && args.has_flag("replace") {
+        eprintln!("Error: use either --merge or --replace");
+        std::process::exit(exitcode::USAGE);
+    }
+    let replace = args.has_flag("replace");
+
+    let content = match std::fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Error reading {}: {}", path, e);
+            std::process::exit(exitcode::NOINPUT);
+        }
+    };
+    let (backup, config_file) = match from_json(&content) {
+        Ok(b) => b,
+        Err(e) => {
+            eprintln!("Error: {}: {}", path, e);
+            std::process::exit(exitcode::DATAERR);
+        }
+    };
+
+    let summary = match db::restore_backup(&backup, replace) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("Error restoring the backup: {}", e);
+            std::process::exit(exitcode::IOERR);
+        }
+    };
+
+    for (from, to) in &summary.renamed {
+        println!(
+            "{} is already used by another bullet, restored as {}",
+            from, to
+        );
+    }
+    println!(
+        "Restored {} bullets, {} already in the journal",
+        summary.restored, summary.existing
+    );
+
+    if let Some(config_file) = config_file {
+        match restore_config(&config_file, replace) {
+            Ok(Some((path, true))) => println!("Replaced the config file {}", path.display()),
+            Ok(Some((path, false))) => println!("Restored the config file {}", path.display()),
+            Ok(None) => {}
+            Err(e) => {
+                eprintln!("Error writing the config file: {}", e);
+                std::process::exit(exitcode::CANTCREAT);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    fn database() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrations::migrate(&mut conn).unwrap();
+        conn
+    }
+
+    fn seed(conn: &Connection) {
+        conn.execute_batch(
+            "INSERT INTO env (key, value) VALUES ('template.short', '{HH}:{MM} {text}');
+            INSERT INTO bjourn (quickid, added, list_date, text, updated, deleted_at, kind, status)
+            VALUES
+                ('beWLHOFj', '2025-01-01 08:12:45', '2025-01-01', 'breakfast with #family',
+                    NULL, NULL, 'note', 'open'),
+                ('Xk2pQ9aa', '2025-01-01 09:00:00', '2025-01-02', 'ship the \"backup\" +work',
+                    '2025-01-02 10:00:00', NULL, 'task', 'done'),
+                ('m0Pq7zZz', '2025-01-03 21:30:00', '2025-01-03', 'removed later',
+                    NULL, '2025-01-04 07:00:00', 'event', 'open');",
+        )
+        .unwrap();
+    }
+
+    fn count(conn: &Connection, sql: &str) -> i64 {
+        conn.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let source = database();
+        seed(&source);
+        let backup = db::backup_from(&source).unwrap();
+        assert_eq!(backup.bullets.len(), 3);
+        assert_eq!(backup.env.len(), 1);
+
+        let json = to_json(&backup, Some("output = \"json\"\n")).to_string();
+        let (read, config_file) = from_json(&json).unwrap();
+        assert_eq!(read, backup);
+        assert_eq!(config_file.as_deref(), Some("output = \"json\"\n"));
+
+        let mut target = database();
+        target
+            .execute(
+                "INSERT INTO bjourn (quickid, added, list_date, text) VALUES ('gone0000', '2024-12-31 00:00:00', '2024-12-31', 'replaced')",
+                [],
+            )
+            .unwrap();
+        let summary = db::restore_into(&mut target, &read, true).unwrap();
+        assert_eq!(summary.restored, 3);
+        assert!(summary.renamed.is_empty());
+        assert_eq!(db::backup_from(&target).unwrap(), backup);
+
+        // tags and the search index are rebuilt from the text
+        assert_eq!(
+            count(
+                &target,
+                "SELECT COUNT(*) FROM bullet_tags bt JOIN tags t ON t.id = bt.tag_id
+                WHERE t.name IN ('#family', '+work')"
+            ),
+            2
+        );
+        assert_eq!(
+            count(
+                &target,
+                "SELECT COUNT(*) FROM bjourn_fts WHERE bjourn_fts MATCH 'breakfast'"
+            ),
+            1
+        );
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut conn = database();
+        seed(&conn);
+        let backup = db::backup_from(&conn).unwrap();
+
+        // merging into the same journal changes nothing
+        let summary = db::restore_into(&mut conn, &backup, false).unwrap();
+        assert_eq!(summary.restored, 0);
+        assert_eq!(summary.existing, 3);
+        assert_eq!(db::backup_from(&conn).unwrap(), backup);
+
+        // a different bullet with the same quickid keeps its quickid, the restored one gets a new one
+        let mut other = database();
+        other
+            .execute_batch(
+                "INSERT INTO env (key, value) VALUES ('template.short', '{text}');
+                INSERT INTO bjourn (quickid, added, list_date, text)
+                VALUES ('beWLHOFj', '2025-02-01 10:00:00', '2025-02-01', 'not breakfast');",
+            )
+            .unwrap();
+        let summary = db::restore_into(&mut other, &backup, false).unwrap();
+        assert_eq!(summary.restored, 3);
+        assert_eq!(summary.renamed.len(), 1);
+        let (from, to) = &summary.renamed[0];
+        assert_eq!(from, "beWLHOFj");
+        assert_ne!(to, "beWLHOFj");
+        assert_eq!(
+            count(
+                &other,
+                "SELECT COUNT(*) FROM bjourn WHERE quickid = 'beWLHOFj' AND text = 'not breakfast'"
+            ),
+            1
+        );
+
+        // the existing env values are kept
+        let merged = db::backup_from(&other).unwrap();
+        assert_eq!(
+            merged.env,
+            vec![("template.short".to_string(), "{text}".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_is_backup_path() {
+        assert!(is_backup_path("backup.json"));
+        assert!(is_backup_path("backup.jsn"));
+        assert!(is_backup_path("backups/bjourn"));
+        assert!(is_backup_path("./bjourn"));
+        assert!(!is_backup_path("beWLHOFj"));
+        assert!(!is_backup_path("beWL"));
+        assert!(!is_backup_path("@-1"));
+        assert!(!is_backup_path("3"));
+    }
+
+    #[test]
+    fn test_from_json_errors() {
+        assert!(from_json("[]").is_err());
+        assert!(from_json("{\"bullets\": []}").is_err());
+        assert!(from_json(&format!(
+            "{{\"schema_version\": {}, \"bullets\": []}}",
+            migrations::latest_version() + 1
+        ))
+        .is_err());
+        assert!(
+            from_json("{\"schema_version\": 1, \"bullets\": [{\"bullet\": \"no quickid\"}]}")
+                .is_err()
+        );
+        assert!(from_json("{\"schema_version\": 1, \"bullets\": []}").is_ok());
+    }
+}
@@ -1,4 +1,7 @@
 // Arguments parser functionallity
+use crate::backup;
+use crate::dates;
+use crate::db;
 use phf::phf_map;
 use std::collections::HashMap;
 use std::io;
@@ -12,11 +15,34 @@ pub enum BAction {
     List,
     ListDefault, // when it's just the default no args passed
     Remove,
+    Edit,
+    Undo,
+    Trash,
+    Restore,
+    Purge,
+    Tags,
+    Done,
+    Cancel,
+    Migrate,
+    Config,
+    Template,
+    Report,
+    Stats,
+    Cal,
+    Tui,
+    Export,
+    Import,
+    Backup,
+    Find,
     Help,
     Version,
 }
 // a list of first arg options enum
-static GLOBAL_ACTIONS: [&str; 5] = ["add", "list", "remove", "help", "version"];
+static GLOBAL_ACTIONS: [&str; 24] = [
+    "add", "list", "remove", "edit", "undo", "trash", "restore", "purge", "tags", "done", "cancel",
+    "migrate", "config", "template", "report", "stats", "cal", "tui", "export", "import", "backup",
+    "find", "help", "version",
+];
 
 // flag map to actions
 static GLOBAL_ACTION_ARGS_MAP: phf::Map<&'static str, BAction> = phf_map! {
@@ -26,6 +52,27 @@ static GLOBAL_ACTION_ARGS_MAP: phf::Map<&'static str, BAction> = phf_map! {
     "list" => BAction::List,
     "r" => BAction::Remove,
     "remove" => BAction::Remove,
+    "e" => BAction::Edit,
+    "edit" => BAction::Edit,
+    "u" => BAction::Undo,
+    "undo" => BAction::Undo,
+    "trash" => BAction::Trash,
+    "restore" => BAction::Restore,
+    "purge" => BAction::Purge,
+    "tags" => BAction::Tags,
+    "done" => BAction::Done,
+    "cancel" => BAction::Cancel,
+    "migrate" => BAction::Migrate,
+    "config" => BAction::Config,
+    "template" => BAction::Template,
+    "report" => BAction::Report,
+    "stats" => BAction::Stats,
+    "cal" => BAction::Cal,
+    "tui" => BAction::Tui,
+    "export" => BAction::Export,
+    "import" => BAction::Import,
+    "backup" => BAction::Backup,
+    "find" => BAction::Find,
     "h" => BAction::Help,
     "help" => BAction::Help,
     "v" => BAction::Version,
@@ -46,6 +93,28 @@ static GLOBAL_ACTION_FLAGS_MAP: phf::Map<&'static str, (&'static str, bool)> = p
     "output" => ("output", true),
     "f" => ("format", true),
     "format" => ("format", true),
+    "since" => ("since", true),
+    "until" => ("until", true),
+    "on" => ("on", true),
+    "date" => ("on", true),
+    "last" => ("last", false),
+    "older-than" => ("older-than", true),
+    "t" => ("tag", true),
+    "tag" => ("tag", true),
+    "k" => ("kind", true),
+    "kind" => ("kind", true),
+    "color" => ("color", true),
+    "columns" => ("columns", true),
+    "week" => ("week", false),
+    "month" => ("month", false),
+    "days" => ("days", true),
+    "pick" => ("pick", false),
+    "dir" => ("dir", true),
+    "dry-run" => ("dry-run", false),
+    "merge" => ("merge", false),
+    "replace" => ("replace", false),
+    "sort" => ("sort", true),
+    "reverse" => ("reverse", false),
 };
 
 fn get_flag_from_flag(flag: &str) -> Option<(&str, bool)> {
@@ -55,6 +124,126 @@ fn get_flag_from_flag(flag: &str) -> Option<(&str, bool)> {
     GLOBAL_ACTION_FLAGS_MAP.get(flag).cloned()
 }
 
+// relative dates like -3d or -2w look like flags but are input
+fn is_relative_date(arg: &str) -> bool {
+    match arg
+        .strip_prefix('-')
+        .and_then(|rest| rest.strip_suffix('d').or_else(|| rest.strip_suffix('w')))
+    {
+        Some(count) => !count.is_empty() && count.chars().all(|c| c.is_ascii_digit()),
+        None => false,
+    }
+}
+
+fn is_flag(arg: &str) -> bool {
+    arg.starts_with("-") && !is_relative_date(arg)
+}
+
+// A bullet given by its place in a day's list, 3 is the third and @-1 the last one
+// plain numbers are shorter than a quickid prefix so the two can't be mixed up
+pub fn parse_position(input: &str) -> Option<i64> {
+    match input.strip_prefix('@') {
+        Some(rest) => rest.parse().ok(),
+        None if input.len() < db::QUICKID_MIN_PREFIX
+            && !input.is_empty()
+            && input.chars().all(|c| c.is_ascii_digit()) =>
+        {
+            input.parse().ok()
+        }
+        None => None,
+    }
+}
+
+// a quickid or the start of one, a short prefix needs a digit or capital to not be a word
+fn is_quickid_like(word: &str) -> bool {
+    let len = word.chars().count();
+    (db::QUICKID_MIN_PREFIX..=8).contains(&len)
+        && word.chars().all(|c| c.is_ascii_alphanumeric())
+        && (len == 8
+            || word
+                .chars()
+                .any(|c| c.is_ascii_digit() || c.is_ascii_uppercase()))
+}
+
+fn is_bullet_ref(word: &str) -> bool {
+    is_quickid_like(word) || parse_position(word).is_some()
+}
+
+// a date or range of dates, e.g. last week or 2025-01-01..today
+fn is_date_input(input: &str) -> bool {
+    let valid = |d: &str| dates::resolve_from(d, dates::today(), chrono::Weekday::Mon).is_ok();
+    match input.split_once("..") {
+        Some((start, end)) => valid(start) && valid(end),
+        None => valid(input),
+    }
+}
+
+// Whether the words after an action given without dashes fit it, e.g. `done 3` does
+fn fits_action(action: &BAction, rest: &[&str]) -> bool {
+    let joined = rest.join(" ");
+    match action {
+        BAction::Add => true,
+        BAction::List | BAction::Cal | BAction::Report => rest.is_empty() || is_date_input(&joined),
+        BAction::Remove | BAction::Done | BAction::Cancel => match rest {
+            [] => true,
+            [word] => is_bullet_ref(word),
+            _ => false,
+        },
+        BAction::Restore => match rest {
+            [] => true,
+            [word] => is_bullet_ref(word) || backup::is_backup_path(word),
+            _ => false,
+        },
+        BAction::Edit => rest.first().is_none_or(|w| is_bullet_ref(w)),
+        BAction::Migrate => match rest {
+            [] => true,
+            [word] => is_bullet_ref(word),
+            [word, date @ ..] => is_bullet_ref(word) && is_date_input(&date.join(" ")),
+        },
+        BAction::Find => !rest.is_empty(),
+        BAction::Import => match rest {
+            [path] => std::path::Path::new(path).exists(),
+            _ => false,
+        },
+        BAction::Config => rest.is_empty() || matches!(rest[0], "get" | "set" | "list"),
+        BAction::Template => {
+            rest.is_empty() || matches!(rest[0], "save" | "list" | "rm" | "remove")
+        }
+        _ => rest.is_empty(),
+    }
+}
+
+// Whether a word could be the start of the input for the action, even a mistyped one like
+// `yesterdy` for list or `abc` for remove, so the action reports it instead of adding a note
+fn starts_like_input(action: &BAction, word: &str) -> bool {
+    let has_digit = word.chars().any(|c| c.is_ascii_digit());
+    match action {
+        BAction::List | BAction::Cal | BAction::Report => {
+            has_digit
+                || word.contains("..")
+                || matches!(word, "this" | "last")
+                || is_date_input(word)
+        }
+        BAction::Remove | BAction::Done | BAction::Cancel | BAction::Edit | BAction::Migrate => {
+            has_digit || is_bullet_ref(word)
+        }
+        BAction::Restore => has_digit || is_bullet_ref(word) || backup::is_backup_path(word),
+        BAction::Import => backup::is_backup_path(word),
+        BAction::Config => matches!(word, "get" | "set" | "list"),
+        BAction::Template => matches!(word, "save" | "list" | "rm" | "remove"),
+        // any text is a query
+        BAction::Find => true,
+        _ => false,
+    }
+}
+
+// A note that starts with an action word, e.g. `done with the quarterly report`. Only prose of a
+// few words that can't be the action's input is added, anything else is left to the action to
+// report, and `bjourn add` always adds
+fn is_prose(action: &BAction, rest: &[&str]) -> bool {
+    rest.len() >= 2 && !fits_action(action, rest) && !starts_like_input(action, rest[0])
+}
+
 pub trait BJournRunner {
     fn parse(args: Vec<String>, input_txt: Option<String>) -> Self;
     fn has_flag(&self, flag: &str) -> bool;
@@ -88,6 +277,10 @@ impl BJournRunner for BArgs {
             };
         }
 
+        // an action given as a plain word, and the words after it
+        let mut bare_action: Option<String> = None;
+        let mut positional: Vec<String> = Vec::new();
+
         let mut skip_next = false;
         for (i, arg) in args.iter().enumerate() {
             // skip the first arg as it is the program name
@@ -115,7 +308,7 @@ impl BJournRunner for BArgs {
                     if takes_arg {
                         if i < args.len() {
                             let next_arg = &args[i + 1];
-                            if !next_arg.starts_with("-") {
+                            if !is_flag(next_arg) {
                                 flag_args.insert(flag_item.to_string(), next_arg.to_string());
                                 skip_next = true;
                             } else {
@@ -133,7 +326,7 @@ impl BJournRunner for BArgs {
                 }
 
                 continue;
-            } else if arg.starts_with("-") && !arg.contains(' ') {
+            } else if is_flag(arg) && !arg.contains(' ') {
                 let flag = arg.replace("-", "");
                 // each character is a flag to set
                 for (x, c) in flag.chars().enumerate() {
@@ -148,7 +341,7 @@ impl BJournRunner for BArgs {
                         if takes_arg && x + 1 == flag.chars().count() {
                             if i < args.len() {
                                 let next_arg = &args[i + 1];
-                                if !next_arg.starts_with("-") {
+                                if !is_flag(next_arg) {
                                     flag_args.insert(flag_item.to_string(), next_arg.to_string());
                                     skip_next = true;
                                 } else {
@@ -178,6 +371,7 @@ impl BJournRunner for BArgs {
                         Some(a) => Some(a),
                         None => Some(BAction::Add),
                     };
+                    bare_action = Some(arg.clone());
                     continue; // dont add to the input
                 } else {
                     action = Some(BAction::Add)
@@ -185,6 +379,7 @@ impl BJournRunner for BArgs {
             }
 
             // we have user input, append each as input
+            positional.push(arg.clone());
             if input.is_none() {
                 input = Some(arg.clone());
             } else {
@@ -195,6 +390,18 @@ impl BJournRunner for BArgs {
             }
         }
 
+        // a note that starts with an action word is added
+        if let (Some(word), Some(a)) = (&bare_action, &action) {
+            let rest: Vec<&str> = positional.iter().map(|p| p.as_str()).collect();
+            if is_prose(a, &rest) {
+                action = Some(BAction::Add);
+                input = Some(match input {
+                    Some(i) => format!("{} {}", word, i),
+                    None => word.clone(),
+                });
+            }
+        }
+
         // default to add if we have input but no action
         let action_default = match input.clone() {
             Some(a) if a.is_empty() => BAction::Add,
@@ -259,9 +466,174 @@ mod tests {
             get_action_from_flag("remove"),
             Some(BAction::Remove)
         ));
+        assert!(matches!(get_action_from_flag("e"), Some(BAction::Edit)));
+        assert!(matches!(get_action_from_flag("edit"), Some(BAction::Edit)));
+        assert!(matches!(get_action_from_flag("undo"), Some(BAction::Undo)));
+        assert!(matches!(
+            get_action_from_flag("trash"),
+            Some(BAction::Trash)
+        ));
+        assert!(matches!(
+            get_action_from_flag("restore"),
+            Some(BAction::Restore)
+        ));
+        assert!(matches!(
+            get_action_from_flag("purge"),
+            Some(BAction::Purge)
+        ));
+        assert!(matches!(get_action_from_flag("tags"), Some(BAction::Tags)));
+        assert!(matches!(get_action_from_flag("done"), Some(BAction::Done)));
+        assert!(matches!(
+            get_action_from_flag("cancel"),
+            Some(BAction::Cancel)
+        ));
+        assert!(matches!(
+            get_action_from_flag("migrate"),
+            Some(BAction::Migrate)
+        ));
+        assert!(matches!(
+            get_action_from_flag("config"),
+            Some(BAction::Config)
+        ));
+        assert!(matches!(
+            get_action_from_flag("template"),
+            Some(BAction::Template)
+        ));
+        assert!(matches!(
+            get_action_from_flag("stats"),
+            Some(BAction::Stats)
+        ));
+        assert!(matches!(get_action_from_flag("cal"), Some(BAction::Cal)));
+        assert!(matches!(get_action_from_flag("tui"), Some(BAction::Tui)));
+        assert!(matches!(
+            get_action_from_flag("export"),
+            Some(BAction::Export)
+        ));
+        assert!(matches!(
+            get_action_from_flag("import"),
+            Some(BAction::Import)
+        ));
+        assert!(matches!(
+            get_action_from_flag("backup"),
+            Some(BAction::Backup)
+        ));
+        assert!(matches!(get_action_from_flag("find"), Some(BAction::Find)));
         assert!(get_action_from_flag("invalid").is_none());
     }
 
+    fn parse(line: &str) -> BArgs {
+        let mut args = vec!["bjourn".to_string()];
+        args.extend(line.split_whitespace().map(|a| a.to_string()));
+        BArgs::parse(args, None)
+    }
+
+    #[test]
+    fn test_action_words_in_notes() {
+        // a note that starts with an action word is still added
+        for line in [
+            "done with the quarterly report",
+            "stats with friends",
+            "cancel the gym membership",
+            "report to the manager tomorrow",
+            "export more coffee beans",
+            "import taxes from last year",
+            "backup the laptop",
+            "tags for the luggage",
+            "migrate the old server tonight",
+            "edit the photos from the trip",
+            "restore the old chair",
+            "list of groceries",
+        ] {
+            let args = parse(line);
+            assert!(matches!(args.action, BAction::Add), "{}", line);
+            assert_eq!(args.input.as_deref(), Some(line));
+        }
+
+        // but the action is used when the rest fits it
+        let args = parse("done beWLHOFj");
+        assert!(matches!(args.action, BAction::Done));
+        assert_eq!(args.input.as_deref(), Some("beWLHOFj"));
+        assert!(matches!(parse("done @-1").action, BAction::Done));
+        assert!(matches!(parse("remove beWL").action, BAction::Remove));
+        assert!(matches!(parse("remove 3").action, BAction::Remove));
+        assert!(matches!(parse("remove --last").action, BAction::Remove));
+        assert!(matches!(parse("edit 2 new text").action, BAction::Edit));
+        assert!(matches!(
+            parse("migrate beWL tomorrow").action,
+            BAction::Migrate
+        ));
+        assert!(matches!(
+            parse("restore backups/bjourn.json").action,
+            BAction::Restore
+        ));
+        assert!(matches!(
+            parse("restore backup.jsn").action,
+            BAction::Restore
+        ));
+        assert!(matches!(parse("stats").action, BAction::Stats));
+        assert!(matches!(parse("stats --days 7").action, BAction::Stats));
+        assert!(matches!(parse("list last week").action, BAction::List));
+        assert!(matches!(
+            parse("list 2025-01-01..today").action,
+            BAction::List
+        ));
+        assert!(matches!(parse("cal 2025-01").action, BAction::Cal));
+        assert!(matches!(parse("find login").action, BAction::Find));
+        assert!(matches!(
+            parse("config set output md").action,
+            BAction::Config
+        ));
+        assert!(matches!(parse("template list").action, BAction::Template));
+
+        // a mistyped input is left to the action to report, not added as a note
+        for (line, action) in [
+            ("list yesterdy", BAction::List),
+            ("list garbage", BAction::List),
+            ("list last weak", BAction::List),
+            ("remove abc", BAction::Remove),
+            ("done 2 things", BAction::Done),
+            ("cal 2025-13", BAction::Cal),
+            ("report lastweek", BAction::Report),
+            ("find", BAction::Find),
+            ("import missing.md", BAction::Import),
+            ("import taxes", BAction::Import),
+        ] {
+            assert_eq!(
+                std::mem::discriminant(&parse(line).action),
+                std::mem::discriminant(&action),
+                "{}",
+                line
+            );
+        }
+
+        // add is always add, and the dashed form is always the action
+        assert!(matches!(
+            parse("add stats with friends").action,
+            BAction::Add
+        ));
+        // find takes any text, so a note starting with find needs the add
+        assert!(matches!(parse("find my keys").action, BAction::Find));
+        let args = parse("add find my keys");
+        assert!(matches!(args.action, BAction::Add));
+        assert_eq!(args.input.as_deref(), Some("find my keys"));
+        let args = parse("--done with the quarterly report");
+        assert!(matches!(args.action, BAction::Done));
+    }
+
+    #[test]
+    fn test_parse_position() {
+        assert_eq!(parse_position("3"), Some(3));
+        assert_eq!(parse_position("12"), Some(12));
+        assert_eq!(parse_position("@2"), Some(2));
+        assert_eq!(parse_position("@-1"), Some(-1));
+        assert_eq!(parse_position("@last"), None);
+        assert_eq!(parse_position("-1"), None);
+        assert_eq!(parse_position(""), None);
+        // long enough to be the start of a quickid
+        assert_eq!(parse_position("1234"), None);
+        assert_eq!(parse_position("beWL"), None);
+    }
+
     #[test]
     fn test_add_command_variants() {
         let args1 = BArgs::parse(
@@ -359,5 +731,217 @@ mod tests {
         assert!(matches!(args2.action, BAction::ListDefault));
         assert_eq!(args2.input, None);
         assert_eq!(args2.flag_arg("output"), Some("json".to_string()));
+
+        let args3 = BArgs::parse(
+            vec![
+                "bjourn".to_string(),
+                "list".to_string(),
+                "--since".to_string(),
+                "2025-01-01".to_string(),
+                "--until".to_string(),
+                "2025-01-07".to_string(),
+            ],
+            None,
+        );
+        assert!(matches!(args3.action, BAction::List));
+        assert_eq!(args3.input, None);
+        assert_eq!(args3.flag_arg("since"), Some("2025-01-01".to_string()));
+        assert_eq!(args3.flag_arg("until"), Some("2025-01-07".to_string()));
+    }
+
+    #[test]
+    fn test_relative_date_inputs() {
+        let args1 = BArgs::parse(
+            vec!["bjourn".to_string(), "list".to_string(), "-3d".to_string()],
+            None,
+        );
+        assert!(matches!(args1.action, BAction::List));
+        assert_eq!(args1.input.unwrap(), "-3d");
+
+        // not a relative date, and must not panic on the multi byte character
+        assert!(is_relative_date("-12w"));
+        assert!(!is_relative_date("-3ü"));
+        assert!(!is_relative_date("-ü"));
+        assert!(!is_relative_date("-d"));
+
+        let args2 = BArgs::parse(
+            vec![
+                "bjourn".to_string(),
+                "list".to_string(),
+                "--since".to_string(),
+                "-2w".to_string(),
+            ],
+            None,
+        );
+        assert!(matches!(args2.action, BAction::List));
+        assert_eq!(args2.input, None);
+        assert_eq!(args2.flag_arg("since"), Some("-2w".to_string()));
+
+        let args3 = BArgs::parse(
+            vec![
+                "bjourn".to_string(),
+                "list".to_string(),
+                "last".to_string(),
+                "monday".to_string(),
+            ],
+            None,
+        );
+        assert_eq!(args3.input.unwrap(), "last monday");
+    }
+
+    #[test]
+    fn test_remove_last() {
+        let args1 = BArgs::parse(
+            vec![
+                "bjourn".to_string(),
+                "remove".to_string(),
+                "--last".to_string(),
+            ],
+            None,
+        );
+        assert!(matches!(args1.action, BAction::Remove));
+        assert!(args1.has_flag("last"));
+        assert_eq!(args1.input, None);
+
+        let args2 = BArgs::parse(vec!["bjourn".to_string(), "undo".to_string()], None);
+        assert!(matches!(args2.action, BAction::Undo));
+        assert_eq!(args2.input, None);
+    }
+
+    #[test]
+    fn test_report_period() {
+        let args1 = BArgs::parse(
+            vec![
+                "bjourn".to_string(),
+                "report".to_string(),
+                "--month".to_string(),
+                "2025-01".to_string(),
+            ],
+            None,
+        );
+        assert!(matches!(args1.action, BAction::Report));
+        assert!(args1.has_flag("month"));
+        assert!(!args1.has_flag("week"));
+        assert_eq!(args1.input.unwrap(), "2025-01");
+
+        let args2 = BArgs::parse(
+            vec![
+                "bjourn".to_string(),
+                "report".to_string(),
+                "--week".to_string(),
+                "-o".to_string(),
+                "json".to_string(),
+            ],
+            None,
+        );
+        assert!(args2.has_flag("week"));
+        assert_eq!(args2.input, None);
+        assert_eq!(args2.flag_arg("output"), Some("json".to_string()));
+    }
+
+    #[test]
+    fn test_purge_older_than() {
+        let args1 = BArgs::parse(
+            vec![
+                "bjourn".to_string(),
+                "purge".to_string(),
+                "--older-than".to_string(),
+                "30d".to_string(),
+            ],
+            None,
+        );
+        assert!(matches!(args1.action, BAction::Purge));
+        assert_eq!(args1.input, None);
+        assert_eq!(args1.flag_arg("older-than"), Some("30d".to_string()));
+    }
+
+    #[test]
+    fn test_tag_filter() {
+        let args1 = BArgs::parse(
+            vec![
+                "bjourn".to_string(),
+                "list".to_string(),
+                "--tag".to_string(),
+                "work".to_string(),
+            ],
+            None,
+        );
+        assert!(matches!(args1.action, BAction::List));
+        assert_eq!(args1.input, None);
+        assert_eq!(args1.flag_arg("tag"), Some("work".to_string()));
+
+        // tags in the text are kept as input
+        let args2 = BArgs::parse(
+            vec![
+                "bjourn".to_string(),
+                "fixed".to_string(),
+                "#work".to_string(),
+                "+clientA".to_string(),
+            ],
+            None,
+        );
+        assert!(matches!(args2.action, BAction::Add));
+        assert_eq!(args2.input.unwrap(), "fixed #work +clientA");
+    }
+
+    #[test]
+    fn test_task_actions() {
+        let args1 = BArgs::parse(
+            vec![
+                "bjourn".to_string(),
+                "-k".to_string(),
+                "task".to_string(),
+                "write".to_string(),
+                "report".to_string(),
+            ],
+            None,
+        );
+        assert!(matches!(args1.action, BAction::Add));
+        assert_eq!(args1.input.clone().unwrap(), "write report");
+        assert_eq!(args1.flag_arg("kind"), Some("task".to_string()));
+
+        let args2 = BArgs::parse(
+            vec![
+                "bjourn".to_string(),
+                "migrate".to_string(),
+                "beWLHOFj".to_string(),
+                "tomorrow".to_string(),
+            ],
+            None,
+        );
+        assert!(matches!(args2.action, BAction::Migrate));
+        assert_eq!(args2.input.unwrap(), "beWLHOFj tomorrow");
+    }
+
+    #[test]
+    fn test_add_on_date() {
+        let args1 = BArgs::parse(
+            vec![
+                "bjourn".to_string(),
+                "add".to_string(),
+                "--on".to_string(),
+                "yesterday".to_string(),
+                "forgot".to_string(),
+                "this".to_string(),
+            ],
+            None,
+        );
+        assert!(matches!(args1.action, BAction::Add));
+        assert_eq!(args1.input.clone().unwrap(), "forgot this");
+        assert_eq!(args1.flag_arg("on"), Some("yesterday".to_string()));
+
+        let args2 = BArgs::parse(
+            vec![
+                "bjourn".to_string(),
+                "forgot".to_string(),
+                "this".to_string(),
+                "--date".to_string(),
+                "2025-01-03".to_string(),
+            ],
+            None,
+        );
+        assert!(matches!(args2.action, BAction::Add));
+        assert_eq!(args2.input.clone().unwrap(), "forgot this");
+        assert_eq!(args2.flag_arg("on"), Some("2025-01-03".to_string()));
     }
 }
@@ -0,0 +1,212 @@
+// Config file support, read from $XDG_CONFIG_HOME/bjourn/config.toml or ~/.config/bjourn/config.toml
+use homedir::my_home;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    pub output: Option<String>,
+    pub format: Option<String>,
+    pub db: Option<String>,
+    pub usage: Option<bool>,
+    pub color: Option<String>,
+    pub week_start: Option<String>,
+}
+
+// the keys that can be set, with a description for the help
+pub static CONFIG_KEYS: [(&str, &str); 6] = [
+    ("output", "default output format for lists e.g. md or json"),
+    ("format", "default format string for the markdown output"),
+    ("db", "path to the database file"),
+    (
+        "usage",
+        "show the usage when running with no arguments, true or false",
+    ),
+    ("color", "auto, always or never"),
+    ("week_start", "first day of the week, monday or sunday"),
+];
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+// The loaded config, read once on first use
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(|| match load() {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Warning: ignoring config file: {}", e);
+            Config::default()
+        }
+    })
+}
+
+pub fn config_location() -> Option<PathBuf> {
+    let mut dir = match std::env::var("XDG_CONFIG_HOME") {
+        Ok(val) if !val.trim().is_empty() => PathBuf::from(val),
+        _ => {
+            let mut home = my_home().ok()??;
+            home.push(".config");
+            home
+        }
+    };
+    dir.push("bjourn");
+    dir.push("config.toml");
+
+    Some(dir)
+}
+
+fn read_table() -> Result<toml::Table, String> {
+    let path = match config_location() {
+        Some(p) => p,
+        None => return Ok(toml::Table::new()),
+    };
+
+    match std::fs::read_to_string(&path) {
+        Ok(content) => content
+            .parse::<toml::Table>()
+            .map_err(|e| format!("{}: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(toml::Table::new()),
+        Err(e) => Err(format!("{}: {}", path.display(), e)),
+    }
+}
+
+fn write_table(table: &toml::Table) -> Result<(), String> {
+    let path = config_location().ok_or("Could not find the config directory")?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    }
+
+    let content = toml::to_string(table).map_err(|e| e.to_string())?;
+    std::fs::write(&path, content).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+pub fn load() -> Result<Config, String> {
+    let table = read_table()?;
+    let string = |key: &str| {
+        table
+            .get(key)
+            .and_then(|v| v.as_str())
+            .map(|v| v.to_string())
+    };
+
+    Ok(Config {
+        output: string("output"),
+        format: string("format"),
+        db: string("db").map(|p| expand_home(&p)),
+        usage: table.get("usage").and_then(|v| v.as_bool()),
+        color: string("color"),
+        week_start: string("week_start"),
+    })
+}
+
+// a leading ~ in a path is the home directory
+fn expand_home(path: &str) -> String {
+    if let Some(rest) = path.strip_prefix("~/") {
+        if let Ok(Some(mut home)) = my_home() {
+            home.push(rest);
+            return home
+                .into_os_string()
+                .into_string()
+                .unwrap_or(path.to_string());
+        }
+    }
+
+    path.to_string()
+}
+
+// Checks a value for a key and converts it to the type stored in the file
+fn parse_value(key: &str, value: &str) -> Result<toml::Value, String> {
+    match key {
+        "usage" => match value {
+            "true" | "1" => Ok(toml::Value::Boolean(true)),
+            "false" | "0" => Ok(toml::Value::Boolean(false)),
+            _ => Err(format!("{} must be true or false", key)),
+        },
+        "color" if !["auto", "always", "never"].contains(&value) => {
+            Err(format!("{} must be auto, always or never", key))
+        }
+        "week_start" if !["monday", "sunday"].contains(&value) => {
+            Err(format!("{} must be monday or sunday", key))
+        }
+        _ => Ok(toml::Value::String(value.to_string())),
+    }
+}
+
+fn check_key(key: &str) -> Result<(), String> {
+    if !CONFIG_KEYS.iter().any(|(k, _)| *k == key) {
+        let keys: Vec<&str> = CONFIG_KEYS.iter().map(|(k, _)| *k).collect();
+        return Err(format!(
+            "Unknown config key {}, use one of {}",
+            key,
+            keys.join(", ")
+        ));
+    }
+
+    Ok(())
+}
+
+// The value of a key as written in the file
+pub fn get_value(key: &str) -> Result<Option<String>, String> {
+    check_key(key)?;
+    let table = read_table()?;
+
+    Ok(table.get(key).map(|v| match v {
+        toml::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }))
+}
+
+pub fn set_value(key: &str, value: &str) -> Result<(), String> {
+    check_key(key)?;
+    let mut table = read_table()?;
+    table.insert(key.to_string(), parse_value(key, value)?);
+
+    write_table(&table)
+}
+
+// All the keys that are set in the file
+pub fn list_values() -> Result<Vec<(String, String)>, String> {
+    let table = read_table()?;
+
+    Ok(table
+        .iter()
+        .map(|(k, v)| {
+            let value = match v {
+                toml::Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            (k.clone(), value)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_value() {
+        assert_eq!(
+            parse_value("usage", "false"),
+            Ok(toml::Value::Boolean(false))
+        );
+        assert!(parse_value("usage", "nope").is_err());
+        assert_eq!(
+            parse_value("color", "never"),
+            Ok(toml::Value::String("never".to_string()))
+        );
+        assert!(parse_value("color", "sometimes").is_err());
+        assert!(parse_value("week_start", "sunday").is_ok());
+        assert!(parse_value("week_start", "friday").is_err());
+        assert_eq!(
+            parse_value("format", "{HH}:{MM} {bullet}"),
+            Ok(toml::Value::String("{HH}:{MM} {bullet}".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_check_key() {
+        assert!(check_key("output").is_ok());
+        assert!(check_key("week_start").is_ok());
+        assert!(check_key("colour").is_err());
+    }
+}
@@ -0,0 +1,291 @@
+// Date resolver, turns user input into a range of days
+use crate::config;
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DateRange {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+}
+
+impl DateRange {
+    fn day(date: NaiveDate) -> Self {
+        DateRange {
+            start: date,
+            end: date,
+        }
+    }
+
+    pub fn start_str(&self) -> String {
+        self.start.format("%Y-%m-%d").to_string()
+    }
+
+    pub fn end_str(&self) -> String {
+        self.end.format("%Y-%m-%d").to_string()
+    }
+
+    pub fn is_single_day(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+pub fn today() -> NaiveDate {
+    chrono::Local::now().date_naive()
+}
+
+// The first day of the week from the config, monday unless set to sunday
+pub fn week_start() -> Weekday {
+    match config::get().week_start.as_deref() {
+        Some("sunday") => Weekday::Sun,
+        _ => Weekday::Mon,
+    }
+}
+
+// Resolves the input relative to the current local day
+pub fn resolve(input: &str) -> Result<DateRange, String> {
+    resolve_from(input, today(), week_start())
+}
+
+// Resolves a single day, inputs that cover more than one day are rejected
+pub fn resolve_day(input: &str) -> Result<NaiveDate, String> {
+    let range = resolve(input)?;
+    if !range.is_single_day() {
+        return Err(format!("'{}' covers more than a single day", input));
+    }
+
+    Ok(range.start)
+}
+
+// Resolves the input to a range of days, relative to `today`
+// supports: YYYY-MM-DD, YYYY-MM, today, yesterday, tomorrow, -3d, -2w,
+// monday, last monday, this week, last week, this month, last month
+pub fn resolve_from(
+    input: &str,
+    today: NaiveDate,
+    week_start: Weekday,
+) -> Result<DateRange, String> {
+    let value = input.trim().to_lowercase();
+    let invalid = || format!("Could not understand the date '{}'", input.trim());
+
+    if let Ok(date) = NaiveDate::parse_from_str(&value, "%Y-%m-%d") {
+        return Ok(DateRange::day(date));
+    }
+
+    // a full month e.g. 2025-01
+    if let Some((year, month)) = value.split_once('-') {
+        if year.len() == 4 && month.len() == 2 {
+            if let (Ok(year), Ok(month)) = (year.parse::<i32>(), month.parse::<u32>()) {
+                let start = NaiveDate::from_ymd_opt(year, month, 1).ok_or_else(invalid)?;
+                return Ok(month_range(start));
+            }
+        }
+    }
+
+    // relative offsets e.g. -3d or -2w
+    if let Some(offset) = value.strip_prefix('-') {
+        let (count, per_unit) = match (offset.strip_suffix('d'), offset.strip_suffix('w')) {
+            (Some(count), _) => (count, 1),
+            (_, Some(count)) => (count, 7),
+            _ => return Err(invalid()),
+        };
+        let days = count
+            .parse::<u64>()
+            .ok()
+            .and_then(|c| c.checked_mul(per_unit))
+            .ok_or_else(invalid)?;
+        let date = today
+            .checked_sub_days(Days::new(days))
+            .ok_or_else(invalid)?;
+        return Ok(DateRange::day(date));
+    }
+
+    match value.split_whitespace().collect::<Vec<&str>>().as_slice() {
+        ["today"] => Ok(DateRange::day(today)),
+        ["yesterday"] => Ok(DateRange::day(today - Days::new(1))),
+        ["tomorrow"] => Ok(DateRange::day(today + Days::new(1))),
+        ["this", "week"] => Ok(week_range(today, week_start)),
+        ["last", "week"] => Ok(week_range(today - Days::new(7), week_start)),
+        ["this", "month"] => Ok(month_range(today.with_day(1).unwrap())),
+        ["last", "month"] => Ok(month_range(today.with_day(1).unwrap() - Months::new(1))),
+        ["last", day] => {
+            let weekday = day.parse::<Weekday>().map_err(|_| invalid())?;
+            Ok(DateRange::day(previous_weekday(today, weekday, false)))
+        }
+        [day] => {
+            let weekday = day.parse::<Weekday>().map_err(|_| invalid())?;
+            Ok(DateRange::day(previous_weekday(today, weekday, true)))
+        }
+        _ => Err(invalid()),
+    }
+}
+
+// the most recent given weekday, optionally counting today
+fn previous_weekday(today: NaiveDate, weekday: Weekday, include_today: bool) -> NaiveDate {
+    let mut back =
+        (7 + today.weekday().num_days_from_monday() - weekday.num_days_from_monday()) % 7;
+    if back == 0 && !include_today {
+        back = 7;
+    }
+
+    today - Days::new(back as u64)
+}
+
+// the week containing the date, starting on the given day
+pub fn week_range(date: NaiveDate, week_start: Weekday) -> DateRange {
+    let start = previous_weekday(date, week_start, true);
+    DateRange {
+        start,
+        end: start + Days::new(6),
+    }
+}
+
+// the month containing the date
+pub fn month_range(date: NaiveDate) -> DateRange {
+    let start = date.with_day(1).unwrap();
+    let end = start + Months::new(1) - Days::new(1);
+    DateRange { start, end }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn day(s: &str) -> DateRange {
+        DateRange::day(d(s))
+    }
+
+    fn range(start: &str, end: &str) -> DateRange {
+        DateRange {
+            start: d(start),
+            end: d(end),
+        }
+    }
+
+    // a wednesday
+    const TODAY: &str = "2025-01-15";
+
+    #[test]
+    fn test_literal_dates() {
+        let today = d(TODAY);
+        assert_eq!(
+            resolve_from("2025-01-01", today, Weekday::Mon),
+            Ok(day("2025-01-01"))
+        );
+        assert_eq!(
+            resolve_from("2025-01", today, Weekday::Mon),
+            Ok(range("2025-01-01", "2025-01-31"))
+        );
+        assert_eq!(
+            resolve_from("2024-02", today, Weekday::Mon),
+            Ok(range("2024-02-01", "2024-02-29"))
+        );
+    }
+
+    #[test]
+    fn test_relative_dates() {
+        let today = d(TODAY);
+        assert_eq!(
+            resolve_from("today", today, Weekday::Mon),
+            Ok(day("2025-01-15"))
+        );
+        assert_eq!(
+            resolve_from(" Today ", today, Weekday::Mon),
+            Ok(day("2025-01-15"))
+        );
+        assert_eq!(
+            resolve_from("yesterday", today, Weekday::Mon),
+            Ok(day("2025-01-14"))
+        );
+        assert_eq!(
+            resolve_from("tomorrow", today, Weekday::Mon),
+            Ok(day("2025-01-16"))
+        );
+        assert_eq!(
+            resolve_from("-3d", today, Weekday::Mon),
+            Ok(day("2025-01-12"))
+        );
+        assert_eq!(
+            resolve_from("-2w", today, Weekday::Mon),
+            Ok(day("2025-01-01"))
+        );
+        assert_eq!(
+            resolve_from("-0d", today, Weekday::Mon),
+            Ok(day("2025-01-15"))
+        );
+    }
+
+    #[test]
+    fn test_weekdays() {
+        let today = d(TODAY);
+        assert_eq!(
+            resolve_from("last monday", today, Weekday::Mon),
+            Ok(day("2025-01-13"))
+        );
+        assert_eq!(
+            resolve_from("last wednesday", today, Weekday::Mon),
+            Ok(day("2025-01-08"))
+        );
+        assert_eq!(
+            resolve_from("wednesday", today, Weekday::Mon),
+            Ok(day("2025-01-15"))
+        );
+        assert_eq!(
+            resolve_from("last fri", today, Weekday::Mon),
+            Ok(day("2025-01-10"))
+        );
+    }
+
+    #[test]
+    fn test_weeks_and_months() {
+        let today = d(TODAY);
+        assert_eq!(
+            resolve_from("this week", today, Weekday::Mon),
+            Ok(range("2025-01-13", "2025-01-19"))
+        );
+        assert_eq!(
+            resolve_from("last week", today, Weekday::Mon),
+            Ok(range("2025-01-06", "2025-01-12"))
+        );
+        assert_eq!(
+            resolve_from("this month", today, Weekday::Mon),
+            Ok(range("2025-01-01", "2025-01-31"))
+        );
+        assert_eq!(
+            resolve_from("last month", today, Weekday::Mon),
+            Ok(range("2024-12-01", "2024-12-31"))
+        );
+    }
+
+    #[test]
+    fn test_week_start_sunday() {
+        let today = d(TODAY);
+        assert_eq!(
+            resolve_from("this week", today, Weekday::Sun),
+            Ok(range("2025-01-12", "2025-01-18"))
+        );
+        assert_eq!(
+            resolve_from("last week", today, Weekday::Sun),
+            Ok(range("2025-01-05", "2025-01-11"))
+        );
+    }
+
+    #[test]
+    fn test_invalid_dates() {
+        let today = d(TODAY);
+        assert!(resolve_from("", today, Weekday::Mon).is_err());
+        assert!(resolve_from("2025-13-01", today, Weekday::Mon).is_err());
+        assert!(resolve_from("2025-13", today, Weekday::Mon).is_err());
+        assert!(resolve_from("2025-02-30", today, Weekday::Mon).is_err());
+        assert!(resolve_from("-3x", today, Weekday::Mon).is_err());
+        assert!(resolve_from("-d", today, Weekday::Mon).is_err());
+        assert!(resolve_from("-3ü", today, Weekday::Mon).is_err());
+        assert!(resolve_from("-ü", today, Weekday::Mon).is_err());
+        assert!(resolve_from("-3üd", today, Weekday::Mon).is_err());
+        assert!(resolve_from("last year", today, Weekday::Mon).is_err());
+        assert!(resolve_from("someday", today, Weekday::Mon).is_err());
+    }
+}
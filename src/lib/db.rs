@@ -1,6 +1,9 @@
+use crate::config;
+use crate::migrations;
+use crate::tags;
 use homedir::my_home;
 use nanoid::nanoid;
-use rusqlite::{params, Connection, Result};
+use rusqlite::{params, Connection, OptionalExtension, Result};
 
 static ALPHABET: [char; 62] = [
     'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's',
@@ -9,111 +12,1172 @@ static ALPHABET: [char; 62] = [
     '5', '6', '7', '8', '9',
 ];
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct BItem {
     pub id: i32,
     pub quickid: String,
     pub added: String,
     pub list_date: String,
     pub text: String,
+    pub updated: Option<String>,
+    pub deleted_at: Option<String>,
+    pub kind: String,
+    pub status: String,
+    pub tags: Vec<String>,
+}
+
+// bullet journal signifiers, every bullet is a note unless added as a task or event
+pub static KINDS: [&str; 3] = ["note", "task", "event"];
+pub static STATUSES: [&str; 4] = ["open", "done", "cancelled", "migrated"];
+
+// the columns read into a BItem, in the order row_to_item expects
+static BITEM_COLUMNS: &str = "bjourn.id, bjourn.quickid, bjourn.text, bjourn.list_date,
+    bjourn.added, bjourn.updated, bjourn.deleted_at, bjourn.kind, bjourn.status,
+    (SELECT GROUP_CONCAT(tags.name, ' ') FROM bullet_tags
+        JOIN tags ON tags.id = bullet_tags.tag_id
+        WHERE bullet_tags.bullet_id = bjourn.id)";
+
+fn row_to_item(row: &rusqlite::Row) -> Result<BItem> {
+    Ok(BItem {
+        id: row.get(0)?,
+        quickid: row.get(1)?,
+        added: row.get(4)?,
+        list_date: row.get(3)?,
+        text: row.get(2)?,
+        updated: row.get(5)?,
+        deleted_at: row.get(6)?,
+        kind: row.get(7)?,
+        status: row.get(8)?,
+        tags: split_tags(row.get(9)?),
+    })
+}
+
+// tag names never contain spaces, so they are concatenated with one
+fn split_tags(tags: Option<String>) -> Vec<String> {
+    let mut tags: Vec<String> = tags
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(|t| t.to_string())
+        .collect();
+    tags.sort_by_key(|t| t.to_lowercase());
+
+    tags
+}
+
+// the ways a list can be sorted, the bullets are always kept together by day
+pub static SORTS: [&str; 4] = ["added", "text", "kind", "status"];
+
+// How a list is sorted, the default is the order the bullets were added in
+// and positions like 3 or @-1 always count in that order
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListOrder {
+    pub sort: String,
+    pub reverse: bool,
+}
+
+impl Default for ListOrder {
+    fn default() -> Self {
+        ListOrder {
+            sort: SORTS[0].to_string(),
+            reverse: false,
+        }
+    }
+}
+
+impl ListOrder {
+    fn order_by(&self) -> String {
+        let column = match self.sort.as_str() {
+            "text" => "bjourn.text COLLATE NOCASE",
+            "kind" => "bjourn.kind",
+            "status" => "bjourn.status",
+            _ => "bjourn.added",
+        };
+        let direction = if self.reverse { "DESC" } else { "ASC" };
+
+        // ties are broken by when the bullet was added so the order never changes between runs
+        ["bjourn.list_date", column, "bjourn.added", "bjourn.id"]
+            .iter()
+            .map(|c| format!("{} {}", c, direction))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
 }
 
 // Lists the bullets for a given day
 pub fn list_bullets(date: &str) -> Result<Vec<BItem>> {
-    let db_path = database_location();
-    let conn = Connection::open(db_path)?;
+    list_bullets_range(date, date, None)
+}
 
-    let mut stmt = conn
-        .prepare("SELECT id, quickid, text, list_date, added FROM bjourn WHERE list_date = ?1")?;
-    let bullet_iter = stmt.query_map(params![date], |row| {
-        Ok(BItem {
-            id: row.get(0)?,
-            quickid: row.get(1)?,
-            added: row.get(4)?,
-            list_date: row.get(3)?,
-            text: row.get(2)?,
-        })
+pub fn list_bullets_in(conn: &Connection, date: &str) -> Result<Vec<BItem>> {
+    let mut bullets = Vec::new();
+    each_bullet_in(conn, date, date, None, &ListOrder::default(), |bullet| {
+        bullets.push(bullet);
+        true
     })?;
 
+    Ok(bullets)
+}
+
+// Lists the bullets between two days (inclusive), ordered by day
+// optionally only the bullets with the given #tag or +context
+pub fn list_bullets_range(start: &str, end: &str, tag: Option<&str>) -> Result<Vec<BItem>> {
     let mut bullets = Vec::new();
+    each_bullet_range(start, end, tag, &ListOrder::default(), |bullet| {
+        bullets.push(bullet);
+        true
+    })?;
+
+    Ok(bullets)
+}
+
+// Streams the bullets between two days (inclusive) to the callback one row at a time,
+// the callback returns false to stop early
+pub fn each_bullet_range<F>(
+    start: &str,
+    end: &str,
+    tag: Option<&str>,
+    order: &ListOrder,
+    callback: F,
+) -> Result<()>
+where
+    F: FnMut(BItem) -> bool,
+{
+    let db_path = database_location();
+    let conn = Connection::open(db_path)?;
+
+    each_bullet_in(&conn, start, end, tag, order, callback)
+}
+
+pub fn each_bullet_in<F>(
+    conn: &Connection,
+    start: &str,
+    end: &str,
+    tag: Option<&str>,
+    order: &ListOrder,
+    mut callback: F,
+) -> Result<()>
+where
+    F: FnMut(BItem) -> bool,
+{
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM bjourn
+        WHERE list_date BETWEEN ?1 AND ?2 AND deleted_at IS NULL
+            AND (?3 IS NULL OR EXISTS (
+                SELECT 1 FROM bullet_tags JOIN tags ON tags.id = bullet_tags.tag_id
+                WHERE bullet_tags.bullet_id = bjourn.id AND tags.name = ?3
+            ))
+        ORDER BY {}",
+        BITEM_COLUMNS,
+        order.order_by()
+    ))?;
+    let bullet_iter = stmt.query_map(params![start, end, tag], row_to_item)?;
+
     for bullet in bullet_iter {
-        bullets.push(bullet?);
+        if !callback(bullet?) {
+            break;
+        }
     }
 
-    Ok(bullets)
+    Ok(())
+}
+
+#[derive(Debug)]
+pub struct BMatch {
+    pub item: BItem,
+    pub snippet: String,
 }
 
-// Adds a bullt with a random nano id
-pub fn add_bullet(text: &String) -> Result<()> {
+// markers placed around the matched terms in a search snippet
+pub static MATCH_START: &str = "\u{2}";
+pub static MATCH_END: &str = "\u{3}";
+
+// Turns what was typed into an FTS5 query, "a phrase" and prefix* are kept and every
+// other word is quoted so punctuation like e-mail or don't is searched for as text
+pub fn fts_query(input: &str) -> String {
+    let quote = |term: &str| format!("\"{}\"", term.replace('"', "\"\""));
+    let mut terms = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+
+        let phrase = c == '"';
+        let mut term = String::new();
+        if phrase {
+            // a phrase runs to the closing quote, or the end when there isn't one
+            for p in chars.by_ref() {
+                if p == '"' {
+                    break;
+                }
+                term.push(p);
+            }
+        } else {
+            term.push(c);
+            while let Some(p) = chars.next_if(|p| !p.is_whitespace()) {
+                term.push(p);
+            }
+        }
+
+        let (term, prefix) = match phrase {
+            true => (term.as_str(), chars.next_if_eq(&'*').is_some()),
+            false => (term.trim_end_matches('*'), term.ends_with('*')),
+        };
+        if term.trim().is_empty() {
+            continue;
+        }
+        terms.push(format!("{}{}", quote(term), if prefix { "*" } else { "" }));
+    }
+
+    terms.join(" ")
+}
+
+// Full text search of the bullets between two days (inclusive)
+// the query supports "a phrase" and prefix*, see fts_query
+pub fn find_bullets(query: &str, start: &str, end: &str) -> Result<Vec<BMatch>> {
     let db_path = database_location();
     let conn = Connection::open(db_path)?;
 
+    find_bullets_in(&conn, query, start, end)
+}
+
+pub fn find_bullets_in(
+    conn: &Connection,
+    query: &str,
+    start: &str,
+    end: &str,
+) -> Result<Vec<BMatch>> {
+    let query = fts_query(query);
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}, snippet(bjourn_fts, 0, ?4, ?5, '…', 12)
+        FROM bjourn_fts
+        JOIN bjourn ON bjourn.id = bjourn_fts.rowid
+        WHERE bjourn_fts MATCH ?1 AND bjourn.list_date BETWEEN ?2 AND ?3
+            AND bjourn.deleted_at IS NULL
+        ORDER BY bjourn.list_date, bjourn.id",
+        BITEM_COLUMNS
+    ))?;
+    let match_iter = stmt.query_map(params![query, start, end, MATCH_START, MATCH_END], |row| {
+        Ok(BMatch {
+            item: row_to_item(row)?,
+            snippet: row.get(10)?,
+        })
+    })?;
+
+    let mut matches = Vec::new();
+    for m in match_iter {
+        matches.push(m?);
+    }
+
+    Ok(matches)
+}
+
+// Adds a bullt with a random nano id, filed under the given day or today
+pub fn add_bullet(text: &str, list_date: Option<&str>, kind: &str) -> Result<()> {
+    let db_path = database_location();
+    let mut conn = Connection::open(db_path)?;
+
+    add_bullet_in(&mut conn, text, list_date, kind)?;
+
+    Ok(())
+}
+
+// returns the id of the new bullet
+pub fn add_bullet_in(
+    conn: &mut Connection,
+    text: &str,
+    list_date: Option<&str>,
+    kind: &str,
+) -> Result<i64> {
+    let tx = conn.transaction()?;
+    let id = insert_bullet(&tx, text, list_date, kind)?;
+    log_op(&tx, "add", id, None)?;
+    tx.commit()?;
+
+    Ok(id)
+}
+
+fn insert_bullet(
+    conn: &Connection,
+    text: &str,
+    list_date: Option<&str>,
+    kind: &str,
+) -> Result<i64> {
+    insert_bullet_with(conn, text, list_date, kind, || nanoid!(8, &ALPHABET))
+}
+
+// how many new quickids are tried before giving up on adding a bullet
+static QUICKID_ATTEMPTS: usize = 10;
+
+// a new quickid can already be taken, the unique index rejects it and another is tried
+fn insert_bullet_with<F>(
+    conn: &Connection,
+    text: &str,
+    list_date: Option<&str>,
+    kind: &str,
+    mut new_quickid: F,
+) -> Result<i64>
+where
+    F: FnMut() -> String,
+{
     let now = chrono::Local::now();
     let now_str = now.format("%Y-%m-%d %H:%M:%S").to_string();
-    let date_str = now.format("%Y-%m-%d").to_string();
-    let quickid = nanoid!(8, &ALPHABET);
+    let date_str = match list_date {
+        Some(d) => d.to_string(),
+        None => now.format("%Y-%m-%d").to_string(),
+    };
+
+    let mut attempt = 1;
+    loop {
+        let inserted = conn.execute(
+            "INSERT INTO bjourn (quickid, added, list_date, text, kind) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![new_quickid(), now_str, date_str, text, kind],
+        );
+        match inserted {
+            Err(rusqlite::Error::SqliteFailure(e, _))
+                if e.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_UNIQUE
+                    && attempt < QUICKID_ATTEMPTS =>
+            {
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+            Ok(_) => break,
+        }
+    }
+    let id = conn.last_insert_rowid();
+    set_tags(conn, id, text)?;
+
+    Ok(id)
+}
+
+// A bullet read from another journal, added is kept when the source has it
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewBullet {
+    pub list_date: String,
+    pub text: String,
+    pub kind: String,
+    pub status: String,
+    pub added: Option<String>,
+}
+
+// Inserts the bullets that aren't already in the journal, a bullet is the same when it has the
+// same day and text (including the trash), returns whether each one is new
+pub fn import_bullets(bullets: &[NewBullet], dry_run: bool) -> Result<Vec<bool>> {
+    let db_path = database_location();
+    let mut conn = Connection::open(db_path)?;
+
+    let tx = conn.transaction()?;
+    let mut seen = std::collections::HashSet::new();
+    let mut new = Vec::new();
+    for bullet in bullets {
+        let exists: bool = tx.query_row(
+            "SELECT EXISTS(SELECT 1 FROM bjourn WHERE list_date = ?1 AND text = ?2)",
+            params![bullet.list_date, bullet.text],
+            |row| row.get(0),
+        )?;
+        let is_new = !exists && seen.insert((bullet.list_date.clone(), bullet.text.clone()));
+        new.push(is_new);
+
+        if is_new && !dry_run {
+            let id = insert_bullet(&tx, &bullet.text, Some(&bullet.list_date), &bullet.kind)?;
+            tx.execute(
+                "UPDATE bjourn SET added = COALESCE(?1, added), status = ?2 WHERE id = ?3",
+                params![bullet.added, bullet.status, id],
+            )?;
+        }
+    }
+    tx.commit()?;
+
+    Ok(new)
+}
 
+// Every row of a bullet, including the trash, as kept in a backup
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackupBullet {
+    pub quickid: String,
+    pub added: String,
+    pub list_date: String,
+    pub text: String,
+    pub updated: Option<String>,
+    pub deleted_at: Option<String>,
+    pub kind: String,
+    pub status: String,
+}
+
+// The journal data of a database, the undo log and search index are not kept
+#[derive(Debug, Clone, PartialEq)]
+pub struct Backup {
+    pub schema_version: i64,
+    pub env: Vec<(String, String)>,
+    pub bullets: Vec<BackupBullet>,
+}
+
+// What a restore did, bullets whose quickid was taken by another bullet get a new one
+#[derive(Debug, Default, PartialEq)]
+pub struct RestoreSummary {
+    pub restored: usize,
+    pub existing: usize,
+    pub renamed: Vec<(String, String)>,
+}
+
+pub fn backup() -> Result<Backup> {
+    let db_path = database_location();
+    let conn = Connection::open(db_path)?;
+
+    backup_from(&conn)
+}
+
+pub fn backup_from(conn: &Connection) -> Result<Backup> {
+    let mut stmt =
+        conn.prepare("SELECT key, value FROM env WHERE key <> 'schema_version' ORDER BY key")?;
+    let env = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<(String, String)>>>()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT quickid, added, list_date, text, updated, deleted_at, kind, status
+        FROM bjourn ORDER BY id",
+    )?;
+    let bullets = stmt
+        .query_map([], |row| {
+            Ok(BackupBullet {
+                quickid: row.get(0)?,
+                added: row.get(1)?,
+                list_date: row.get(2)?,
+                text: row.get(3)?,
+                updated: row.get(4)?,
+                deleted_at: row.get(5)?,
+                kind: row.get(6)?,
+                status: row.get(7)?,
+            })
+        })?
+        .collect::<Result<Vec<BackupBullet>>>()?;
+
+    Ok(Backup {
+        schema_version: migrations::schema_version(conn)?,
+        env,
+        bullets,
+    })
+}
+
+pub fn restore_backup(backup: &Backup, replace: bool) -> Result<RestoreSummary> {
+    let db_path = database_location();
+    let mut conn = Connection::open(db_path)?;
+
+    restore_into(&mut conn, backup, replace)
+}
+
+// Restores a backup in one transaction, replace empties the journal first, otherwise the
+// backup is merged in and a bullet with the same quickid and added time is already there
+pub fn restore_into(
+    conn: &mut Connection,
+    backup: &Backup,
+    replace: bool,
+) -> Result<RestoreSummary> {
+    let tx = conn.transaction()?;
+    if replace {
+        // the undo log points at rows that are going away
+        tx.execute_batch(
+            "DELETE FROM bullet_tags;
+            DELETE FROM tags;
+            DELETE FROM bjourn;
+            DELETE FROM oplog;
+            DELETE FROM env WHERE key <> 'schema_version';",
+        )?;
+    }
+
+    for (key, value) in &backup.env {
+        if key != "schema_version" {
+            tx.execute(
+                "INSERT OR IGNORE INTO env (key, value) VALUES (?1, ?2)",
+                params![key, value],
+            )?;
+        }
+    }
+
+    let mut summary = RestoreSummary::default();
+    for bullet in &backup.bullets {
+        let existing: Option<String> = tx
+            .query_row(
+                "SELECT added FROM bjourn WHERE quickid = ?1",
+                params![bullet.quickid],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        let quickid = match existing {
+            Some(added) if added == bullet.added => {
+                summary.existing += 1;
+                continue;
+            }
+            Some(_) => {
+                let quickid = unused_quickid(&tx)?;
+                summary
+                    .renamed
+                    .push((bullet.quickid.clone(), quickid.clone()));
+                quickid
+            }
+            None => bullet.quickid.clone(),
+        };
+
+        tx.execute(
+            "INSERT INTO bjourn (quickid, added, list_date, text, updated, deleted_at, kind, status)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                quickid,
+                bullet.added,
+                bullet.list_date,
+                bullet.text,
+                bullet.updated,
+                bullet.deleted_at,
+                bullet.kind,
+                bullet.status
+            ],
+        )?;
+        set_tags(&tx, tx.last_insert_rowid(), &bullet.text)?;
+        summary.restored += 1;
+    }
+    tx.commit()?;
+
+    Ok(summary)
+}
+
+// a random quickid that no bullet has yet
+fn unused_quickid(conn: &Connection) -> Result<String> {
+    loop {
+        let quickid = nanoid!(8, &ALPHABET);
+        let taken: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM bjourn WHERE quickid = ?1)",
+            params![quickid],
+            |row| row.get(0),
+        )?;
+        if !taken {
+            return Ok(quickid);
+        }
+    }
+}
+
+// Sets the status of a bullet e.g. done or cancelled, returns the number of rows changed
+pub fn set_status(quickid: &str, status: &str) -> Result<usize> {
+    let db_path = database_location();
+    let mut conn = Connection::open(db_path)?;
+
+    set_status_in(&mut conn, quickid, status)
+}
+
+pub fn set_status_in(conn: &mut Connection, quickid: &str, status: &str) -> Result<usize> {
+    let tx = conn.transaction()?;
+    let bullet = match get_live_bullet(&tx, quickid)? {
+        Some(b) => b,
+        None => return Ok(0),
+    };
+    log_op(&tx, "status", bullet.id as i64, Some(&bullet))?;
+    let changed = tx.execute(
+        "UPDATE bjourn SET status = ?1 WHERE id = ?2",
+        params![status, bullet.id],
+    )?;
+    tx.commit()?;
+
+    Ok(changed)
+}
+
+// Moves an open task forward to another day, the original is kept and marked as migrated
+// returns the new bullet
+pub fn migrate_bullet(quickid: &str, list_date: &str) -> Result<Option<BItem>> {
+    let db_path = database_location();
+    let mut conn = Connection::open(db_path)?;
+
+    migrate_bullet_in(&mut conn, quickid, list_date)
+}
+
+pub fn migrate_bullet_in(
+    conn: &mut Connection,
+    quickid: &str,
+    list_date: &str,
+) -> Result<Option<BItem>> {
+    let tx = conn.transaction()?;
+    let bullet = match get_live_bullet(&tx, quickid)? {
+        Some(b) => b,
+        None => return Ok(None),
+    };
+    let id = insert_bullet(&tx, &bullet.text, Some(list_date), &bullet.kind)?;
+    tx.execute(
+        "UPDATE bjourn SET status = 'migrated' WHERE id = ?1",
+        params![bullet.id],
+    )?;
+    log_op_data(
+        &tx,
+        "migrate",
+        bullet.id as i64,
+        Some(&bullet),
+        serde_json::json!({ "migrated_to": id }),
+    )?;
+    let migrated = get_bullet_by(&tx, "id", &id)?;
+    tx.commit()?;
+
+    Ok(migrated)
+}
+
+fn get_bullet_by(
+    conn: &Connection,
+    column: &str,
+    value: &dyn rusqlite::ToSql,
+) -> Result<Option<BItem>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM bjourn WHERE {} = ?1",
+        BITEM_COLUMNS, column
+    ))?;
+    let mut bullet_iter = stmt.query_map(params![value], row_to_item)?;
+
+    bullet_iter.next().transpose()
+}
+
+// Links the bullet to the #tags and +contexts in its text, replacing any it had
+fn set_tags(conn: &Connection, bullet_id: i64, text: &str) -> Result<()> {
     conn.execute(
-        "INSERT INTO bjourn (quickid, added, list_date, text) VALUES (?1, ?2, ?3, ?4)",
-        params![quickid, now_str, date_str, text],
+        "DELETE FROM bullet_tags WHERE bullet_id = ?1",
+        params![bullet_id],
     )?;
 
+    for tag in tags::parse_tags(text) {
+        conn.execute(
+            "INSERT OR IGNORE INTO tags (name) VALUES (?1)",
+            params![tag],
+        )?;
+        conn.execute(
+            "INSERT OR IGNORE INTO bullet_tags (bullet_id, tag_id)
+            SELECT ?1, id FROM tags WHERE name = ?2",
+            params![bullet_id, tag],
+        )?;
+    }
+
     Ok(())
 }
 
-// remove a bullet item based on quickid
-pub fn remove_bullet(quickid: &String) -> Result<()> {
+// Counts the bullets for each tag, most used first
+pub fn tag_counts() -> Result<Vec<(String, i64)>> {
+    let db_path = database_location();
+    let conn = Connection::open(db_path)?;
+
+    let mut stmt = conn.prepare(
+        "SELECT tags.name, COUNT(*) FROM tags
+        JOIN bullet_tags ON bullet_tags.tag_id = tags.id
+        JOIN bjourn ON bjourn.id = bullet_tags.bullet_id
+        WHERE bjourn.deleted_at IS NULL
+        GROUP BY tags.id
+        ORDER BY COUNT(*) DESC, tags.name",
+    )?;
+    let tag_iter = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+    let mut counts = Vec::new();
+    for tag in tag_iter {
+        counts.push(tag?);
+    }
+
+    Ok(counts)
+}
+
+// How many bullets there are on each day between two days (inclusive), days without any are left out
+pub fn count_by_date(start: &str, end: &str) -> Result<Vec<(String, i64)>> {
     let db_path = database_location();
     let conn = Connection::open(db_path)?;
 
-    conn.execute("DELETE FROM bjourn WHERE quickid = ?1", params![quickid])?;
+    count_by_date_in(&conn, start, end)
+}
+
+pub fn count_by_date_in(conn: &Connection, start: &str, end: &str) -> Result<Vec<(String, i64)>> {
+    let mut stmt = conn.prepare(
+        "SELECT list_date, COUNT(*) FROM bjourn
+        WHERE list_date BETWEEN ?1 AND ?2 AND deleted_at IS NULL
+        GROUP BY list_date
+        ORDER BY list_date",
+    )?;
+    let count_iter = stmt.query_map(params![start, end], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+    let mut counts = Vec::new();
+    for count in count_iter {
+        counts.push(count?);
+    }
+
+    Ok(counts)
+}
+
+// The added timestamps of the bullets that are not in the trash, oldest first
+pub fn added_times() -> Result<Vec<String>> {
+    let db_path = database_location();
+    let conn = Connection::open(db_path)?;
+
+    let mut stmt =
+        conn.prepare("SELECT added FROM bjourn WHERE deleted_at IS NULL ORDER BY added")?;
+    let added_iter = stmt.query_map([], |row| row.get(0))?;
+
+    let mut added = Vec::new();
+    for a in added_iter {
+        added.push(a?);
+    }
+
+    Ok(added)
+}
+
+// saved output templates are kept in the env table with this prefix on the key
+static TEMPLATE_PREFIX: &str = "template.";
+
+// Saves a named output template, replacing one with the same name
+pub fn save_template(name: &str, format: &str) -> Result<()> {
+    let db_path = database_location();
+    let conn = Connection::open(db_path)?;
+
+    conn.execute(
+        "INSERT INTO env (key, value) VALUES (?1, ?2)
+        ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        params![format!("{}{}", TEMPLATE_PREFIX, name), format],
+    )?;
 
     Ok(())
 }
 
-// Creates and sets up the DB schema
-pub fn create_database() -> Result<()> {
+pub fn get_template(name: &str) -> Result<Option<String>> {
     let db_path = database_location();
+    let conn = Connection::open(db_path)?;
 
-    // Connect to SQLite database (creates the file if it doesn't exist)
+    conn.query_row(
+        "SELECT value FROM env WHERE key = ?1",
+        params![format!("{}{}", TEMPLATE_PREFIX, name)],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+// The saved templates ordered by name
+pub fn list_templates() -> Result<Vec<(String, String)>> {
+    let db_path = database_location();
+    let conn = Connection::open(db_path)?;
+
+    let mut stmt = conn.prepare(
+        "SELECT substr(key, ?1), value FROM env
+        WHERE substr(key, 1, ?2) = ?3
+        ORDER BY key",
+    )?;
+    let template_iter = stmt.query_map(
+        params![
+            TEMPLATE_PREFIX.len() + 1,
+            TEMPLATE_PREFIX.len(),
+            TEMPLATE_PREFIX
+        ],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+
+    let mut templates = Vec::new();
+    for template in template_iter {
+        templates.push(template?);
+    }
+
+    Ok(templates)
+}
+
+pub fn remove_template(name: &str) -> Result<usize> {
+    let db_path = database_location();
     let conn = Connection::open(db_path)?;
 
-    // Create an env table for settings
     conn.execute(
-        "CREATE TABLE IF NOT EXISTS env (
-          id INTEGER PRIMARY KEY AUTOINCREMENT,
-          key TEXT NOT NULL,
-          value TEXT NOT NULL
-      )",
-        [], // No parameters needed
+        "DELETE FROM env WHERE key = ?1",
+        params![format!("{}{}", TEMPLATE_PREFIX, name)],
+    )
+}
+
+// the shortest part of a quickid that can be used for it, like a git short hash
+pub static QUICKID_MIN_PREFIX: usize = 4;
+
+#[derive(Debug, PartialEq)]
+pub enum QuickidMatch {
+    None,
+    One(String),
+    Ambiguous(Vec<String>),
+}
+
+// Finds the quickid a full quickid or the start of one refers to, either in the journal or
+// in the trash
+pub fn match_quickid(input: &str, trash: bool) -> Result<QuickidMatch> {
+    let db_path = database_location();
+    let conn = Connection::open(db_path)?;
+
+    match_quickid_in(&conn, input, trash)
+}
+
+pub fn match_quickid_in(conn: &Connection, input: &str, trash: bool) -> Result<QuickidMatch> {
+    if input.chars().count() < QUICKID_MIN_PREFIX {
+        return Ok(QuickidMatch::None);
+    }
+
+    // substr keeps the match case sensitive, LIKE would not be
+    let mut stmt = conn.prepare(
+        "SELECT quickid FROM bjourn
+        WHERE substr(quickid, 1, length(?1)) = ?1 AND (deleted_at IS NOT NULL) = ?2
+        ORDER BY quickid = ?1 DESC, quickid",
     )?;
+    let quickids = stmt
+        .query_map(params![input, trash], |row| row.get(0))?
+        .collect::<Result<Vec<String>>>()?;
+
+    Ok(match quickids.as_slice() {
+        [] => QuickidMatch::None,
+        [first, ..] if first == input => QuickidMatch::One(first.clone()),
+        [only] => QuickidMatch::One(only.clone()),
+        _ => QuickidMatch::Ambiguous(quickids),
+    })
+}
+
+// The quickid of the bullet at a position in the day's list, counting from 1,
+// negative positions count back from the end
+pub fn quickid_at(list_date: &str, position: i64) -> Result<Option<String>> {
+    let db_path = database_location();
+    let conn = Connection::open(db_path)?;
+
+    quickid_at_in(&conn, list_date, position)
+}
+
+pub fn quickid_at_in(conn: &Connection, list_date: &str, position: i64) -> Result<Option<String>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT quickid FROM bjourn WHERE list_date = ?1 AND deleted_at IS NULL ORDER BY {}",
+        ListOrder::default().order_by()
+    ))?;
+    let quickids = stmt
+        .query_map(params![list_date], |row| row.get(0))?
+        .collect::<Result<Vec<String>>>()?;
+
+    let index = match position {
+        p if p > 0 => p - 1,
+        p if p < 0 => quickids.len() as i64 + p,
+        _ => return Ok(None),
+    };
+
+    Ok(usize::try_from(index)
+        .ok()
+        .and_then(|i| quickids.get(i).cloned()))
+}
+
+// Gets a single bullet by quickid, bullets in the trash are not included
+pub fn get_bullet(quickid: &str) -> Result<Option<BItem>> {
+    let db_path = database_location();
+    let conn = Connection::open(db_path)?;
+
+    get_live_bullet(&conn, quickid)
+}
+
+fn get_live_bullet(conn: &Connection, quickid: &str) -> Result<Option<BItem>> {
+    Ok(get_bullet_by(conn, "quickid", &quickid)?.filter(|b| b.deleted_at.is_none()))
+}
+
+// Replaces the text of a bullet, keeping its ids and date, returns the number of rows changed
+pub fn edit_bullet(quickid: &str, text: &str) -> Result<usize> {
+    let db_path = database_location();
+    let mut conn = Connection::open(db_path)?;
+
+    edit_bullet_in(&mut conn, quickid, text)
+}
+
+pub fn edit_bullet_in(conn: &mut Connection, quickid: &str, text: &str) -> Result<usize> {
+    let now_str = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+
+    let tx = conn.transaction()?;
+    let bullet = match get_live_bullet(&tx, quickid)? {
+        Some(b) => b,
+        None => return Ok(0),
+    };
+    log_op(&tx, "edit", bullet.id as i64, Some(&bullet))?;
+    let changed = tx.execute(
+        "UPDATE bjourn SET text = ?1, updated = ?2 WHERE id = ?3",
+        params![text, now_str, bullet.id],
+    )?;
+    set_tags(&tx, bullet.id as i64, text)?;
+    tx.commit()?;
+
+    Ok(changed)
+}
+
+// moves the bullet to the trash, logging it so it can be undone
+fn delete_logged(conn: &Connection, bullet: &BItem) -> Result<()> {
+    let now_str = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
 
-    // Insert the current version if no key exists
+    log_op(conn, "remove", bullet.id as i64, Some(bullet))?;
     conn.execute(
-        "INSERT OR IGNORE INTO env (key, value) VALUES ('version', '0.1.0')",
-        [], // No parameters needed
+        "UPDATE bjourn SET deleted_at = ?1 WHERE id = ?2",
+        params![now_str, bullet.id],
     )?;
 
-    // Create the bullet list
+    Ok(())
+}
+
+// moves a bullet to the trash based on quickid, returns the number of rows removed
+pub fn remove_bullet(quickid: &str) -> Result<usize> {
+    let db_path = database_location();
+    let mut conn = Connection::open(db_path)?;
+
+    remove_bullet_in(&mut conn, quickid)
+}
+
+pub fn remove_bullet_in(conn: &mut Connection, quickid: &str) -> Result<usize> {
+    let tx = conn.transaction()?;
+    let bullet = get_live_bullet(&tx, quickid)?;
+    if let Some(ref b) = bullet {
+        delete_logged(&tx, b)?;
+    }
+    tx.commit()?;
+
+    Ok(bullet.map_or(0, |_| 1))
+}
+
+// Lists the bullets in the trash, most recently removed first
+pub fn list_trash() -> Result<Vec<BItem>> {
+    let db_path = database_location();
+    let conn = Connection::open(db_path)?;
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM bjourn
+        WHERE deleted_at IS NOT NULL
+        ORDER BY deleted_at DESC, id DESC",
+        BITEM_COLUMNS
+    ))?;
+    let bullet_iter = stmt.query_map([], row_to_item)?;
+
+    let mut bullets = Vec::new();
+    for bullet in bullet_iter {
+        bullets.push(bullet?);
+    }
+
+    Ok(bullets)
+}
+
+// Takes a bullet back out of the trash, returning it
+pub fn restore_bullet(quickid: &str) -> Result<Option<BItem>> {
+    let db_path = database_location();
+    let mut conn = Connection::open(db_path)?;
+
+    restore_bullet_in(&mut conn, quickid)
+}
+
+pub fn restore_bullet_in(conn: &mut Connection, quickid: &str) -> Result<Option<BItem>> {
+    let tx = conn.transaction()?;
+    let bullet = get_bullet_by(&tx, "quickid", &quickid)?.filter(|b| b.deleted_at.is_some());
+    if let Some(ref b) = bullet {
+        log_op(&tx, "restore", b.id as i64, Some(b))?;
+        tx.execute(
+            "UPDATE bjourn SET deleted_at = NULL WHERE id = ?1",
+            params![b.id],
+        )?;
+    }
+    tx.commit()?;
+
+    Ok(bullet)
+}
+
+// Permanently deletes bullets removed before the given time, returns the number deleted
+pub fn purge_trash(before: &str) -> Result<usize> {
+    let db_path = database_location();
+    let mut conn = Connection::open(db_path)?;
+
+    purge_trash_in(&mut conn, before)
+}
+
+pub fn purge_trash_in(conn: &mut Connection, before: &str) -> Result<usize> {
+    let tx = conn.transaction()?;
+    tx.execute(
+        "DELETE FROM oplog WHERE bullet_id IN
+            (SELECT id FROM bjourn WHERE deleted_at IS NOT NULL AND deleted_at < ?1)",
+        params![before],
+    )?;
+    tx.execute(
+        "DELETE FROM bullet_tags WHERE bullet_id IN
+            (SELECT id FROM bjourn WHERE deleted_at IS NOT NULL AND deleted_at < ?1)",
+        params![before],
+    )?;
+    let purged = tx.execute(
+        "DELETE FROM bjourn WHERE deleted_at IS NOT NULL AND deleted_at < ?1",
+        params![before],
+    )?;
+    tx.commit()?;
+
+    Ok(purged)
+}
+
+// Removes the most recently added bullet, returning it
+pub fn remove_last_bullet() -> Result<Option<BItem>> {
+    let db_path = database_location();
+    let mut conn = Connection::open(db_path)?;
+
+    let tx = conn.transaction()?;
+    let bullet = tx
+        .query_row(
+            &format!(
+                "SELECT {} FROM bjourn WHERE deleted_at IS NULL
+                ORDER BY added DESC, id DESC LIMIT 1",
+                BITEM_COLUMNS
+            ),
+            [],
+            row_to_item,
+        )
+        .optional()?;
+    if let Some(ref b) = bullet {
+        delete_logged(&tx, b)?;
+    }
+    tx.commit()?;
+
+    Ok(bullet)
+}
+
+// Records a change so it can be undone, `before` is the bullet before the change
+fn log_op(conn: &Connection, op: &str, bullet_id: i64, before: Option<&BItem>) -> Result<()> {
+    log_op_data(conn, op, bullet_id, before, serde_json::json!({}))
+}
+
+// Records a change with extra data needed to undo it
+fn log_op_data(
+    conn: &Connection,
+    op: &str,
+    bullet_id: i64,
+    before: Option<&BItem>,
+    extra: serde_json::Value,
+) -> Result<()> {
+    let data = before.map(|b| {
+        let mut data = serde_json::json!({
+            "quickid": b.quickid,
+            "added": b.added,
+            "list_date": b.list_date,
+            "text": b.text,
+            "updated": b.updated,
+            "deleted_at": b.deleted_at,
+            "kind": b.kind,
+            "status": b.status,
+        });
+        if let (Some(data), Some(extra)) = (data.as_object_mut(), extra.as_object()) {
+            data.extend(extra.clone());
+        }
+        data.to_string()
+    });
+    let now_str = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+
     conn.execute(
-        "CREATE TABLE IF NOT EXISTS bjourn (
-          id INTEGER PRIMARY KEY AUTOINCREMENT,
-          quickid TEXT NOT NULL,
-          added TEXT NOT NULL,
-          list_date TEXT NOT NULL,
-          text TEXT NOT NULL
-      )",
-        [], // No parameters needed
+        "INSERT INTO oplog (op, bullet_id, data, created) VALUES (?1, ?2, ?3, ?4)",
+        params![op, bullet_id, data, now_str],
     )?;
 
     Ok(())
 }
 
+// Reverses the last change to a bullet, returning the operation and the bullet as it is now
+pub fn undo() -> Result<Option<(String, BItem)>> {
+    let db_path = database_location();
+    let mut conn = Connection::open(db_path)?;
+
+    undo_in(&mut conn)
+}
+
+pub fn undo_in(conn: &mut Connection) -> Result<Option<(String, BItem)>> {
+    let tx = conn.transaction()?;
+    let last = tx
+        .query_row(
+            "SELECT id, op, bullet_id, data FROM oplog ORDER BY id DESC LIMIT 1",
+            [],
+            |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                ))
+            },
+        )
+        .optional()?;
+    let (log_id, op, bullet_id, data) = match last {
+        Some(l) => l,
+        None => return Ok(None),
+    };
+    let before: serde_json::Value = data
+        .map(|d| serde_json::from_str(&d).unwrap_or_default())
+        .unwrap_or_default();
+    let field = |name: &str| before[name].as_str().map(|v| v.to_string());
+
+    let now_str = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    match op.as_str() {
+        "add" => {
+            tx.execute(
+                "UPDATE bjourn SET deleted_at = ?1 WHERE id = ?2",
+                params![now_str, bullet_id],
+            )?;
+        }
+        "remove" => {
+            tx.execute(
+                "UPDATE bjourn SET deleted_at = NULL WHERE id = ?1",
+                params![bullet_id],
+            )?;
+        }
+        "restore" => {
+            tx.execute(
+                "UPDATE bjourn SET deleted_at = ?1 WHERE id = ?2",
+                params![field("deleted_at"), bullet_id],
+            )?;
+        }
+        "status" => {
+            tx.execute(
+                "UPDATE bjourn SET status = ?1 WHERE id = ?2",
+                params![field("status"), bullet_id],
+            )?;
+        }
+        "migrate" => {
+            tx.execute(
+                "UPDATE bjourn SET status = ?1 WHERE id = ?2",
+                params![field("status"), bullet_id],
+            )?;
+            // the copy is deleted outright, in the trash it could be restored as a duplicate
+            let copy = before["migrated_to"].as_i64();
+            tx.execute(
+                "DELETE FROM bullet_tags WHERE bullet_id = ?1",
+                params![copy],
+            )?;
+            tx.execute("DELETE FROM oplog WHERE bullet_id = ?1", params![copy])?;
+            tx.execute("DELETE FROM bjourn WHERE id = ?1", params![copy])?;
+        }
+        "edit" => {
+            tx.execute(
+                "UPDATE bjourn SET text = ?1, updated = ?2 WHERE id = ?3",
+                params![field("text"), field("updated"), bullet_id],
+            )?;
+            set_tags(&tx, bullet_id, &field("text").unwrap_or_default())?;
+        }
+        _ => {}
+    }
+
+    tx.execute("DELETE FROM oplog WHERE id = ?1", params![log_id])?;
+    let bullet = get_bullet_by(&tx, "id", &bullet_id)?;
+    tx.commit()?;
+
+    Ok(bullet.map(|b| (op, b)))
+}
+
+// Creates the database and brings the schema up to date
+pub fn create_database() -> std::result::Result<(), migrations::MigrationError> {
+    let db_path = database_location();
+
+    // Connect to SQLite database (creates the file if it doesn't exist)
+    let mut conn = Connection::open(db_path)?;
+
+    migrations::migrate(&mut conn)?;
+
+    Ok(())
+}
+
 pub fn database_location() -> String {
     let db_path: String;
 
     if std::env::var("BJOURN_DB").is_ok() {
         db_path = std::env::var("BJOURN_DB").unwrap();
+    } else if let Some(path) = &config::get().db {
+        db_path = path.clone();
     } else {
         let db_dir = my_home().unwrap();
         match db_dir {
@@ -130,3 +1194,436 @@ pub fn database_location() -> String {
 
     db_path
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn database() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrations::migrate(&mut conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO bjourn (quickid, added, list_date, text, deleted_at) VALUES
+                ('beWLHOFj', '2025-01-01 08:12:45', '2025-01-01', 'breakfast', NULL),
+                ('beWLxk2p', '2025-01-01 09:00:00', '2025-01-01', 'coffee', NULL),
+                ('beWL', '2025-01-01 09:30:00', '2025-01-01', 'short', NULL),
+                ('ZScG1V3i', '2025-01-01 13:02:11', '2025-01-01', 'lunch', NULL),
+                ('k9TQ2mXa', '2025-01-02 09:30:00', '2025-01-02', 'removed', '2025-01-03 10:00:00');",
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_match_quickid() {
+        let conn = database();
+        let matched = |input: &str, trash: bool| match_quickid_in(&conn, input, trash).unwrap();
+
+        assert_eq!(
+            matched("ZScG1V3i", false),
+            QuickidMatch::One("ZScG1V3i".to_string())
+        );
+        assert_eq!(
+            matched("ZScG", false),
+            QuickidMatch::One("ZScG1V3i".to_string())
+        );
+        assert_eq!(
+            matched("beWLH", false),
+            QuickidMatch::One("beWLHOFj".to_string())
+        );
+        // a full quickid wins over the longer ones it is the start of
+        assert_eq!(
+            matched("beWL", false),
+            QuickidMatch::One("beWL".to_string())
+        );
+        assert_eq!(
+            matched("beW", false),
+            QuickidMatch::None,
+            "shorter than the minimum prefix"
+        );
+        assert_eq!(matched("zscg", false), QuickidMatch::None);
+
+        // bullets in the trash are only found when looking in the trash
+        assert_eq!(matched("k9TQ", false), QuickidMatch::None);
+        assert_eq!(
+            matched("k9TQ", true),
+            QuickidMatch::One("k9TQ2mXa".to_string())
+        );
+
+        conn.execute("DELETE FROM bjourn WHERE quickid = 'beWL'", [])
+            .unwrap();
+        assert_eq!(
+            matched("beWL", false),
+            QuickidMatch::Ambiguous(vec!["beWLHOFj".to_string(), "beWLxk2p".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_quickid_at() {
+        let conn = database();
+        // added out of order, the list is in the order they were added
+        conn.execute(
+            "INSERT INTO bjourn (quickid, added, list_date, text)
+            VALUES ('early000', '2025-01-01 07:00:00', '2025-01-01', 'woke up')",
+            [],
+        )
+        .unwrap();
+        let at = |position: i64| quickid_at_in(&conn, "2025-01-01", position).unwrap();
+
+        assert_eq!(at(1).as_deref(), Some("early000"));
+        assert_eq!(at(2).as_deref(), Some("beWLHOFj"));
+        assert_eq!(at(-1).as_deref(), Some("ZScG1V3i"));
+        assert_eq!(at(-5).as_deref(), Some("early000"));
+        assert_eq!(at(0), None);
+        assert_eq!(at(6), None);
+        assert_eq!(at(-6), None);
+
+        // the trash is not counted
+        assert_eq!(quickid_at_in(&conn, "2025-01-02", 1).unwrap(), None);
+    }
+
+    fn listed(conn: &Connection, sort: &str, reverse: bool) -> Vec<String> {
+        let order = ListOrder {
+            sort: sort.to_string(),
+            reverse,
+        };
+        let mut texts = Vec::new();
+        each_bullet_in(conn, "2025-01-01", "2025-01-02", None, &order, |b| {
+            texts.push(b.text);
+            true
+        })
+        .unwrap();
+        texts
+    }
+
+    #[test]
+    fn test_list_order() {
+        let conn = database();
+        // inserted after the others but added earlier, and on the first day
+        conn.execute_batch(
+            "UPDATE bjourn SET deleted_at = NULL WHERE quickid = 'k9TQ2mXa';
+            INSERT INTO bjourn (quickid, added, list_date, text, kind, status) VALUES
+                ('late0000', '2025-01-03 08:00:00', '2025-01-01', 'Added late', 'task', 'done'),
+                ('early000', '2025-01-01 07:00:00', '2025-01-01', 'woke up', 'event', 'open');
+            UPDATE bjourn SET kind = 'task' WHERE quickid = 'ZScG1V3i';",
+        )
+        .unwrap();
+
+        // chronological by default, whatever order the rows were inserted in
+        assert_eq!(
+            listed(&conn, "added", false),
+            vec![
+                "woke up",
+                "breakfast",
+                "coffee",
+                "short",
+                "lunch",
+                "Added late",
+                "removed"
+            ]
+        );
+        assert_eq!(
+            listed(&conn, "added", true),
+            vec![
+                "removed",
+                "Added late",
+                "lunch",
+                "short",
+                "coffee",
+                "breakfast",
+                "woke up"
+            ]
+        );
+
+        // the days stay together, sorted within each day
+        assert_eq!(
+            listed(&conn, "text", false),
+            vec![
+                "Added late",
+                "breakfast",
+                "coffee",
+                "lunch",
+                "short",
+                "woke up",
+                "removed"
+            ]
+        );
+        assert_eq!(
+            listed(&conn, "kind", false),
+            vec![
+                "woke up",
+                "breakfast",
+                "coffee",
+                "short",
+                "lunch",
+                "Added late",
+                "removed"
+            ]
+        );
+        assert_eq!(
+            listed(&conn, "status", false),
+            vec![
+                "Added late",
+                "woke up",
+                "breakfast",
+                "coffee",
+                "short",
+                "lunch",
+                "removed"
+            ]
+        );
+    }
+
+    fn found(conn: &Connection, query: &str, start: &str, end: &str) -> Vec<String> {
+        find_bullets_in(conn, query, start, end)
+            .unwrap()
+            .into_iter()
+            .map(|m| m.item.text)
+            .collect()
+    }
+
+    #[test]
+    fn test_fts_query() {
+        assert_eq!(fts_query("login"), "\"login\"");
+        assert_eq!(fts_query("e-mail  don't"), "\"e-mail\" \"don't\"");
+        assert_eq!(
+            fts_query("\"login bug\" deploy*"),
+            "\"login bug\" \"deploy\"*"
+        );
+        assert_eq!(fts_query("\"login bu\"*"), "\"login bu\"*");
+        assert_eq!(fts_query("say\"hi"), "\"say\"\"hi\"");
+        assert_eq!(fts_query("\"unclosed phrase"), "\"unclosed phrase\"");
+        assert_eq!(fts_query("NOT OR"), "\"NOT\" \"OR\"");
+        assert_eq!(fts_query(" * \"\" "), "");
+    }
+
+    #[test]
+    fn test_find_bullets() {
+        let mut conn = database();
+        conn.execute_batch(
+            "INSERT INTO bjourn (quickid, added, list_date, text) VALUES
+                ('mail0000', '2025-01-02 10:00:00', '2025-01-02', 'sent the e-mail about the login bug'),
+                ('dont0000', '2025-01-05 10:00:00', '2025-01-05', 'don''t deploy on fridays'),
+                ('depl0000', '2025-01-06 10:00:00', '2025-01-06', 'deployed the login fix');",
+        )
+        .unwrap();
+        let all = |conn: &Connection, query: &str| found(conn, query, "0001-01-01", "9999-12-31");
+
+        // punctuation is searched for as text instead of being FTS5 syntax
+        assert_eq!(
+            all(&conn, "e-mail"),
+            vec!["sent the e-mail about the login bug"]
+        );
+        assert_eq!(all(&conn, "don't"), vec!["don't deploy on fridays"]);
+
+        // phrases and prefixes
+        assert_eq!(
+            all(&conn, "\"login bug\""),
+            vec!["sent the e-mail about the login bug"]
+        );
+        assert_eq!(all(&conn, "\"bug login\""), Vec::<String>::new());
+        assert_eq!(
+            all(&conn, "deploy*"),
+            vec!["don't deploy on fridays", "deployed the login fix"]
+        );
+        assert_eq!(all(&conn, "deploy"), vec!["don't deploy on fridays"]);
+
+        // only the days in the range
+        assert_eq!(
+            found(&conn, "login", "2025-01-03", "2025-01-06"),
+            vec!["deployed the login fix"]
+        );
+        assert_eq!(
+            found(&conn, "login", "2025-01-01", "2025-01-05"),
+            vec!["sent the e-mail about the login bug"]
+        );
+
+        // the trash is not searched
+        assert_eq!(all(&conn, "removed"), Vec::<String>::new());
+
+        // the index follows edits
+        edit_bullet_in(&mut conn, "depl0000", "rolled back the release").unwrap();
+        assert_eq!(all(&conn, "deployed"), Vec::<String>::new());
+        assert_eq!(all(&conn, "rolled"), vec!["rolled back the release"]);
+    }
+
+    fn bullet(conn: &Connection, id: i64) -> Option<BItem> {
+        get_bullet_by(conn, "id", &id).unwrap()
+    }
+
+    fn quickid(conn: &Connection, id: i64) -> String {
+        bullet(conn, id).unwrap().quickid
+    }
+
+    fn count(conn: &Connection, sql: &str) -> i64 {
+        conn.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn test_undo_add_and_remove() {
+        let mut conn = database();
+        let id =
+            add_bullet_in(&mut conn, "planning the #week", Some("2025-01-02"), "note").unwrap();
+
+        let (op, undone) = undo_in(&mut conn).unwrap().unwrap();
+        assert_eq!(op, "add");
+        assert!(undone.deleted_at.is_some());
+        assert!(get_live_bullet(&conn, &undone.quickid).unwrap().is_none());
+
+        // nothing left to undo
+        assert!(undo_in(&mut conn).unwrap().is_none());
+
+        assert_eq!(remove_bullet_in(&mut conn, "ZScG1V3i").unwrap(), 1);
+        assert!(get_live_bullet(&conn, "ZScG1V3i").unwrap().is_none());
+        let (op, undone) = undo_in(&mut conn).unwrap().unwrap();
+        assert_eq!(op, "remove");
+        assert_eq!(undone.quickid, "ZScG1V3i");
+        assert!(undone.deleted_at.is_none());
+        assert!(bullet(&conn, id).unwrap().deleted_at.is_some());
+    }
+
+    #[test]
+    fn test_undo_edit() {
+        let mut conn = database();
+        let id = add_bullet_in(&mut conn, "breakfast with #family", None, "note").unwrap();
+        let quickid = quickid(&conn, id);
+
+        edit_bullet_in(&mut conn, &quickid, "lunch with +work").unwrap();
+        assert_eq!(bullet(&conn, id).unwrap().tags, vec!["+work"]);
+
+        let (op, undone) = undo_in(&mut conn).unwrap().unwrap();
+        assert_eq!(op, "edit");
+        assert_eq!(undone.text, "breakfast with #family");
+        assert_eq!(undone.tags, vec!["#family"]);
+        assert!(undone.updated.is_none());
+    }
+
+    #[test]
+    fn test_undo_status() {
+        let mut conn = database();
+        let id = add_bullet_in(&mut conn, "ship it", None, "task").unwrap();
+        let quickid = quickid(&conn, id);
+
+        set_status_in(&mut conn, &quickid, "done").unwrap();
+        assert_eq!(bullet(&conn, id).unwrap().status, "done");
+
+        let (op, undone) = undo_in(&mut conn).unwrap().unwrap();
+        assert_eq!(op, "status");
+        assert_eq!(undone.status, "open");
+    }
+
+    #[test]
+    fn test_undo_migrate() {
+        let mut conn = database();
+        let id = add_bullet_in(&mut conn, "file the #taxes", Some("2025-01-01"), "task").unwrap();
+        let quickid = quickid(&conn, id);
+
+        let copy = migrate_bullet_in(&mut conn, &quickid, "2025-01-02")
+            .unwrap()
+            .unwrap();
+        assert_eq!(copy.list_date, "2025-01-02");
+        assert_eq!(bullet(&conn, id).unwrap().status, "migrated");
+
+        let (op, undone) = undo_in(&mut conn).unwrap().unwrap();
+        assert_eq!(op, "migrate");
+        assert_eq!(undone.status, "open");
+
+        // the copy is gone, not in the trash where it could be restored
+        assert!(bullet(&conn, copy.id as i64).is_none());
+        assert_eq!(
+            match_quickid_in(&conn, &copy.quickid, true).unwrap(),
+            QuickidMatch::None
+        );
+        assert_eq!(
+            count(
+                &conn,
+                &format!(
+                    "SELECT COUNT(*) FROM bullet_tags WHERE bullet_id = {}",
+                    copy.id
+                )
+            ),
+            0
+        );
+    }
+
+    #[test]
+    fn test_remove_and_restore() {
+        let mut conn = database();
+        let all = |conn: &Connection| listed(conn, "added", false);
+        let lunch = |conn: &Connection| found(conn, "lunch", "0001-01-01", "9999-12-31");
+
+        remove_bullet_in(&mut conn, "ZScG1V3i").unwrap();
+        assert!(!all(&conn).contains(&"lunch".to_string()));
+        assert!(lunch(&conn).is_empty());
+        // already in the trash
+        assert_eq!(remove_bullet_in(&mut conn, "ZScG1V3i").unwrap(), 0);
+
+        let restored = restore_bullet_in(&mut conn, "ZScG1V3i").unwrap().unwrap();
+        assert_eq!(restored.text, "lunch");
+        assert!(all(&conn).contains(&"lunch".to_string()));
+        assert_eq!(lunch(&conn), vec!["lunch"]);
+
+        // only bullets in the trash can be restored
+        assert!(restore_bullet_in(&mut conn, "ZScG1V3i").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_purge_trash() {
+        let mut conn = database();
+        let old = add_bullet_in(&mut conn, "old #news", Some("2025-01-01"), "note").unwrap();
+        let recent = add_bullet_in(&mut conn, "recent #news", Some("2025-01-01"), "note").unwrap();
+        for id in [old, recent] {
+            let quickid = quickid(&conn, id);
+            remove_bullet_in(&mut conn, &quickid).unwrap();
+        }
+        conn.execute_batch(&format!(
+            "UPDATE bjourn SET deleted_at = '2025-01-01 10:00:00' WHERE id = {};
+            UPDATE bjourn SET deleted_at = '2025-03-01 10:00:00' WHERE id = {};",
+            old, recent
+        ))
+        .unwrap();
+        let rows = |conn: &Connection, table: &str, id: i64| {
+            let column = if table == "bjourn" { "id" } else { "bullet_id" };
+            count(
+                conn,
+                &format!("SELECT COUNT(*) FROM {} WHERE {} = {}", table, column, id),
+            )
+        };
+        assert_eq!(rows(&conn, "oplog", old), 2);
+        assert_eq!(rows(&conn, "bullet_tags", old), 1);
+
+        // k9TQ2mXa was removed on 2025-01-03
+        assert_eq!(purge_trash_in(&mut conn, "2025-02-01 00:00:00").unwrap(), 2);
+        for table in ["bjourn", "oplog", "bullet_tags"] {
+            assert_eq!(rows(&conn, table, old), 0, "{}", table);
+        }
+        assert!(get_bullet_by(&conn, "quickid", &"k9TQ2mXa")
+            .unwrap()
+            .is_none());
+
+        // the newer one is still in the trash and can be restored
+        assert_eq!(rows(&conn, "bjourn", recent), 1);
+        assert_eq!(rows(&conn, "bullet_tags", recent), 1);
+        let quickid = quickid(&conn, recent);
+        assert!(restore_bullet_in(&mut conn, &quickid).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_insert_retries_taken_quickid() {
+        let conn = database();
+        let mut quickids = vec!["fresh000", "ZScG1V3i", "beWLHOFj"];
+        let id = insert_bullet_with(&conn, "dinner", None, "note", || {
+            quickids.pop().unwrap().to_string()
+        })
+        .unwrap();
+        assert_eq!(
+            get_bullet_by(&conn, "id", &id).unwrap().unwrap().quickid,
+            "fresh000"
+        );
+
+        // it gives up rather than trying forever
+        assert!(
+            insert_bullet_with(&conn, "dinner", None, "note", || "ZScG1V3i".to_string()).is_err()
+        );
+    }
+}
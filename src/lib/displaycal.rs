@@ -0,0 +1,282 @@
+// Calendar view of a month, like cal(1), with the days that have entries highlighted
+use crate::bargs;
+use crate::bargs::BJournRunner;
+use crate::config;
+use crate::dates;
+use crate::db;
+use crate::formatters;
+use chrono::{Datelike, Days, NaiveDate, Weekday};
+use colored::{ColoredString, Colorize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::io::IsTerminal;
+
+// The weeks of the month, each a row of 7 days starting on week_start, None outside the month
+pub fn month_grid(month: NaiveDate, week_start: Weekday) -> Vec<[Option<NaiveDate>; 7]> {
+    let range = dates::month_range(month);
+    let mut weeks = Vec::new();
+    let mut day = dates::week_range(range.start, week_start).start;
+
+    while day <= range.end {
+        let mut week = [None; 7];
+        for cell in week.iter_mut() {
+            if day >= range.start && day <= range.end {
+                *cell = Some(day);
+            }
+            day = day + Days::new(1);
+        }
+        weeks.push(week);
+    }
+
+    weeks
+}
+
+// 0 for no entries up to 4 for the busiest days of the month
+pub fn intensity(count: i64, max: i64) -> usize {
+    if count <= 0 || max <= 0 {
+        return 0;
+    }
+
+    (((count * 4) + max - 1) / max).clamp(1, 4) as usize
+}
+
+// The shortest list input that resolves to the day, e.g. today, friday or 2025-01-03
+pub fn quickest_ref(date: NaiveDate, today: NaiveDate) -> String {
+    if date == today {
+        return "today".to_string();
+    }
+    if date + Days::new(1) == today {
+        return "yesterday".to_string();
+    }
+    if date == today + Days::new(1) {
+        return "tomorrow".to_string();
+    }
+    // a weekday name is the most recent one, so it works for the last week
+    if date < today && today - Days::new(7) < date {
+        return date.format("%A").to_string().to_lowercase();
+    }
+
+    date.format("%Y-%m-%d").to_string()
+}
+
+fn styled_day(cell: String, level: usize) -> ColoredString {
+    match level {
+        0 => cell.normal(),
+        1 => cell.green(),
+        2 => cell.bright_green(),
+        3 => cell.bright_green().bold(),
+        _ => cell.black().on_bright_green(),
+    }
+}
+
+fn displaycal_grid(
+    month: NaiveDate,
+    week_start: Weekday,
+    counts: &HashMap<NaiveDate, i64>,
+    today: NaiveDate,
+) {
+    let terminal = std::io::stdout().is_terminal();
+    let max = counts.values().copied().max().unwrap_or(0);
+
+    let title = format!("{:^20}", month.format("%B %Y").to_string());
+    if terminal {
+        println!("{}", title.trim_end().bold());
+    } else {
+        println!("{}", title.trim_end());
+    }
+
+    let mut weekday = week_start;
+    let mut names = Vec::new();
+    for _ in 0..7 {
+        names.push(weekday.to_string()[..2].to_string());
+        weekday = weekday.succ();
+    }
+    println!("{}", names.join(" "));
+
+    for week in month_grid(month, week_start) {
+        let mut line = String::new();
+        for (i, cell) in week.iter().enumerate() {
+            if i > 0 {
+                line.push(' ');
+            }
+            let day = match cell {
+                Some(d) => d,
+                None => {
+                    line.push_str("  ");
+                    continue;
+                }
+            };
+
+            let count = counts.get(day).copied().unwrap_or(0);
+            let text = format!("{:>2}", day.day());
+            if terminal {
+                let mut styled = styled_day(text, intensity(count, max));
+                if *day == today {
+                    styled = styled.reversed().underline();
+                }
+                line.push_str(&styled.to_string());
+            } else {
+                // like cal(1), colors and today are only shown in the terminal
+                line.push_str(&text);
+            }
+        }
+        println!("{}", line.trim_end());
+    }
+
+    if !terminal {
+        // the days with entries, so piped output is still useful
+        println!();
+        let mut days: Vec<(&NaiveDate, &i64)> = counts.iter().collect();
+        days.sort();
+        for (day, count) in days {
+            println!("* {} ({})", day, count);
+        }
+    }
+}
+
+// the list command for each day with entries
+fn displaycal_pick(counts: &HashMap<NaiveDate, i64>, today: NaiveDate) {
+    let mut days: Vec<(&NaiveDate, &i64)> = counts.iter().collect();
+    days.sort();
+
+    for (day, count) in days {
+        let command = format!("bjourn list {}", quickest_ref(*day, today));
+        if std::io::stdout().is_terminal() {
+            println!(
+                "{} {} {}",
+                day.to_string().bold(),
+                format!("({})", count).dimmed(),
+                command.green()
+            );
+        } else {
+            println!("{} ({}) {}", day, count, command);
+        }
+    }
+}
+
+fn displaycal_json(counts: &HashMap<NaiveDate, i64>, today: NaiveDate) {
+    let mut days: Vec<(&NaiveDate, &i64)> = counts.iter().collect();
+    days.sort();
+
+    let items: Vec<serde_json::Value> = days
+        .iter()
+        .map(|(day, count)| {
+            json!({
+                "date": day.to_string(),
+                "count": count,
+                "list": quickest_ref(**day, today),
+            })
+        })
+        .collect();
+
+    println!("{}", json!(items));
+}
+
+pub fn displaycal(args: &bargs::BArgs) {
+    let format = match formatters::summary_format(
+        args.flag_arg("output").as_deref(),
+        config::get().output.as_deref(),
+    ) {
+        Ok(f) => f,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(exitcode::USAGE);
+        }
+    };
+
+    let today = dates::today();
+    let month = match args.input {
+        Some(ref m) => match dates::resolve(m) {
+            Ok(r) => r.start,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(exitcode::USAGE);
+            }
+        },
+        None => today,
+    };
+    let range = dates::month_range(month);
+
+    let counts: HashMap<NaiveDate, i64> =
+        match db::count_by_date(&range.start_str(), &range.end_str()) {
+            Ok(c) => c
+                .into_iter()
+                .filter_map(|(d, count)| {
+                    NaiveDate::parse_from_str(&d, "%Y-%m-%d")
+                        .ok()
+                        .map(|d| (d, count))
+                })
+                .collect(),
+            Err(e) => {
+                eprintln!("Error counting bullets: {}", e);
+                std::process::exit(exitcode::IOERR);
+            }
+        };
+
+    match format {
+        formatters::SummaryFormat::Json => displaycal_json(&counts, today),
+        formatters::SummaryFormat::Markdown if args.has_flag("pick") => {
+            displaycal_pick(&counts, today)
+        }
+        formatters::SummaryFormat::Markdown => {
+            displaycal_grid(range.start, dates::week_start(), &counts, today)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_month_grid() {
+        // january 2025 starts on a wednesday
+        let weeks = month_grid(d("2025-01-15"), Weekday::Mon);
+        assert_eq!(weeks.len(), 5);
+        assert_eq!(weeks[0][0], None);
+        assert_eq!(weeks[0][2], Some(d("2025-01-01")));
+        assert_eq!(weeks[4][4], Some(d("2025-01-31")));
+        assert_eq!(weeks[4][5], None);
+
+        let weeks = month_grid(d("2025-01-01"), Weekday::Sun);
+        assert_eq!(weeks[0][3], Some(d("2025-01-01")));
+
+        // february 2021 fits in exactly four weeks
+        assert_eq!(month_grid(d("2021-02-01"), Weekday::Mon).len(), 4);
+    }
+
+    #[test]
+    fn test_intensity() {
+        assert_eq!(intensity(0, 8), 0);
+        assert_eq!(intensity(1, 8), 1);
+        assert_eq!(intensity(3, 8), 2);
+        assert_eq!(intensity(6, 8), 3);
+        assert_eq!(intensity(8, 8), 4);
+        assert_eq!(intensity(1, 1), 4);
+    }
+
+    #[test]
+    fn test_quickest_ref() {
+        // a wednesday
+        let today = d("2025-01-15");
+        assert_eq!(quickest_ref(today, today), "today");
+        assert_eq!(quickest_ref(d("2025-01-14"), today), "yesterday");
+        assert_eq!(quickest_ref(d("2025-01-16"), today), "tomorrow");
+        assert_eq!(quickest_ref(d("2025-01-10"), today), "friday");
+        assert_eq!(quickest_ref(d("2025-01-08"), today), "2025-01-08");
+        assert_eq!(quickest_ref(d("2025-01-20"), today), "2025-01-20");
+
+        // each shortcut resolves back to the same day
+        for day in ["2025-01-09", "2025-01-10", "2025-01-13", "2025-01-16"] {
+            let input = quickest_ref(d(day), today);
+            assert_eq!(
+                dates::resolve_from(&input, today, Weekday::Mon),
+                dates::resolve_from(day, today, Weekday::Mon)
+            );
+        }
+    }
+}
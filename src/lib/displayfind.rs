@@ -0,0 +1,124 @@
+use crate::bargs;
+use crate::bargs::BJournRunner;
+use crate::config;
+use crate::dates;
+use crate::db;
+use crate::formatters;
+use colored::Colorize;
+use serde_json::json;
+use std::io::IsTerminal;
+
+// swaps the match markers in a snippet for the given highlight
+fn highlight(snippet: &str, highlight: impl Fn(&str) -> String) -> String {
+    let mut content = String::new();
+    let mut rest = snippet;
+
+    while let Some(start) = rest.find(db::MATCH_START) {
+        content.push_str(&rest[..start]);
+        rest = &rest[start + db::MATCH_START.len()..];
+
+        let end = rest.find(db::MATCH_END).unwrap_or(rest.len());
+        content.push_str(&highlight(&rest[..end]));
+        rest = &rest[(end + db::MATCH_END.len()).min(rest.len())..];
+    }
+    content.push_str(rest);
+
+    content
+}
+
+fn displayfind_md(list: Vec<db::BMatch>) {
+    for m in list {
+        if std::io::stdout().is_terminal() {
+            println!(
+                "{} {} {}: {}",
+                "*".bold(),
+                m.item.list_date.dimmed(),
+                m.item.quickid.magenta(),
+                highlight(&m.snippet, |t| t.yellow().bold().to_string())
+            );
+        } else {
+            // for piping output
+            println!(
+                "* {} {}: {}",
+                m.item.list_date,
+                m.item.quickid,
+                highlight(&m.snippet, |t| format!("**{}**", t))
+            );
+        }
+    }
+}
+
+fn displayfind_json(list: Vec<db::BMatch>) {
+    let mut items = Vec::new();
+    for m in list {
+        items.push(json!({
+            "quickid": m.item.quickid,
+            "bullet": m.item.text,
+            "date": m.item.list_date,
+            "added": m.item.added,
+            "tags": m.item.tags,
+            "kind": m.item.kind,
+            "status": m.item.status,
+            "snippet": highlight(&m.snippet, |t| format!("**{}**", t)),
+        }));
+    }
+
+    println!("{}", json!(items));
+}
+
+// the days to search, everything unless limited with --since and --until
+fn find_range(args: &bargs::BArgs) -> Result<(String, String), String> {
+    let start = match args.flag_arg("since") {
+        Some(since) => dates::resolve(&since)?.start_str(),
+        None => "0001-01-01".to_string(),
+    };
+
+    let end = match args.flag_arg("until") {
+        Some(until) => dates::resolve(&until)?.end_str(),
+        None => "9999-12-31".to_string(),
+    };
+
+    Ok((start, end))
+}
+
+pub fn displayfind(args: &bargs::BArgs) {
+    let format = match formatters::summary_format(
+        args.flag_arg("output").as_deref(),
+        config::get().output.as_deref(),
+    ) {
+        Ok(f) => f,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(exitcode::USAGE);
+        }
+    };
+
+    let query = match &args.input {
+        Some(q) => q,
+        None => {
+            eprintln!("Error: find requires a search query");
+            std::process::exit(exitcode::USAGE);
+        }
+    };
+
+    let (start, end) = match find_range(args) {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(exitcode::USAGE);
+        }
+    };
+
+    let list = match db::find_bullets(query, &start, &end) {
+        Ok(l) => l,
+        Err(e) => {
+            eprintln!("Error searching bullets: {}", e);
+            std::process::exit(exitcode::IOERR);
+        }
+    };
+
+    match format {
+        formatters::SummaryFormat::Markdown => displayfind_md(list),
+        formatters::SummaryFormat::Json => displayfind_json(list),
+    }
+}
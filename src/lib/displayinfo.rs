@@ -1,3 +1,6 @@
+use crate::config;
+use crate::formatters;
+use crate::template;
 use colored::Colorize;
 
 pub fn usage() {
@@ -16,7 +19,10 @@ pub fn usage() {
     println!("\t{}", "bjourn add my entry here".bold());
     println!("\t{}", "bjourn remove ZScG1V3i".bold());
     println!();
-    println!("Actions: {}", "add, list, remove".bold().italic());
+    println!(
+        "Actions: {}",
+        "add, list, remove, edit, undo, trash, find".bold().italic()
+    );
     println!(
         "\t{} {}",
         "bjourn help".bold(),
@@ -45,14 +51,105 @@ pub fn help() {
     println!("{}", "\t-a, --add, add [text]".green());
     println!("\t\tAdd a new entry with the given text");
 
+    println!("{}", "\t-e, --edit, edit [id] [optional text]".green());
+    println!("\t\tReplace the text of the entry, opens $EDITOR when no text is given");
+
+    println!("{}", "\t--done, done [id]".green());
+    println!("\t\tMark an open task as done");
+
+    println!("{}", "\t--cancel, cancel [id]".green());
+    println!("\t\tMark an open task as cancelled");
+
+    println!("{}", "\t--migrate, migrate [id] [optional date]".green());
+    println!("\t\tMove an open task to another day, defaults to today");
+
+    println!(
+        "{}",
+        "\t--config, config [get key | set key value | list]".green()
+    );
+    println!("\t\tRead and change the config file, flags on the command line take precedence");
+    for (key, description) in config::CONFIG_KEYS {
+        println!("\t\t\t {} - {}", key, description);
+    }
+
+    println!(
+        "{}",
+        "\t--template, template [save name format | list | rm name]".green()
+    );
+    println!("\t\tSave named formats for the list action, use them with -f @name");
+    for (name, format) in template::BUILTIN_TEMPLATES.iter() {
+        println!("\t\t\t @{} = {}", name, format);
+    }
+
+    println!("{}", "\t--tags, tags".green());
+    println!("\t\tList the #tags and +contexts used with how many entries have them");
+
+    println!(
+        "{}",
+        "\t--report, report [--week | --month] [optional date]".green()
+    );
+    println!("\t\tA digest of the week or month by day and by tag, with the open tasks");
+
+    println!("{}", "\t--stats, stats [--days 30]".green());
+    println!("\t\tTotals, entries per day, logging streaks, top tags and the busiest hour");
+
+    println!("{}", "\t--cal, cal [optional month] [--pick]".green());
+    println!("\t\tA calendar of the month with the days that have entries highlighted");
+    println!("\t\t--pick prints the list command for each of those days");
+
+    println!("{}", "\t--tui, tui".green());
+    println!("\t\tFull screen mode for browsing and editing, needs the tui feature");
+
+    println!(
+        "{}",
+        "\t--export, export --dir [directory] [--since date] [--until date]".green()
+    );
+    println!("\t\tWrite a markdown file for each day, only files that changed are rewritten");
+
+    println!(
+        "{}",
+        "\t--import, import [file or directory] [--dry-run]".green()
+    );
+    println!("\t\tImport markdown, jrnl or json files, entries already in the journal are skipped");
+
+    println!("{}", "\t--find, find [query]".green());
+    println!("\t\tSearch all entries, supports \"phrase queries\" and prefix* matching");
+
     println!("{}", "\t-h, --help, help".green());
     println!("\t\tPrint this help message");
 
     println!("{}", "\t-l, --list, list [optional date]".green());
     println!("\t\tList all entries for the given date, defaults to today");
+    println!("\t\tA range of dates can be given as 2025-01-01..2025-01-07");
+    println!("\t\tDates can be relative e.g. yesterday, -3d, last monday, last week, 2025-01");
+    println!(
+        "\t\tSorted by when they were added, --sort text, kind or status and --reverse change it"
+    );
 
     println!("{}", "\t-r, --remove, remove [id]".green());
-    println!("\t\tRemove the entry with the given id");
+    println!("\t\tRemove the entry with the given id, --last removes the newest entry");
+    println!("\t\tAn id can be shortened to its first 4 or more characters when it is unique, or be a position in the list like 3 or @-1 for the last");
+
+    println!("{}", "\t--trash, trash".green());
+    println!("\t\tList the removed entries");
+
+    println!("{}", "\t--restore, restore [id]".green());
+    println!("\t\tRestore a removed entry from the trash");
+
+    println!("{}", "\t--backup, backup".green());
+    println!("\t\tPrint every entry, the saved templates and the config as json");
+
+    println!(
+        "{}",
+        "\t--restore, restore [backup file] [--merge | --replace]".green()
+    );
+    println!("\t\tRestore a backup, merged into the journal unless --replace is set");
+
+    println!("{}", "\t--purge, purge [--older-than 30d]".green());
+    println!("\t\tPermanently delete the entries in the trash");
+
+    println!("{}", "\t-u, --undo, undo".green());
+    println!("\t\tUndo the last add, remove, restore or edit");
 
     println!("{}", "\t-v, --version, version".green());
     println!("\t\tPrint the version of bjourn");
@@ -61,19 +158,43 @@ pub fn help() {
 
     println!("{}", "OPTIONS:".yellow());
 
-    println!("{}", "\t-o, --output [md, markdown, json]".green());
+    println!(
+        "{}",
+        format!("\t-o, --output [{}]", formatters::available().join(", ")).green()
+    );
     println!("\t\tOutput format for the list action, defaults to markdown");
 
+    println!("{}", "\t-k, --kind [note, task, event]".green());
+    println!("\t\tThe kind of entry to add, defaults to note");
+
+    println!("{}", "\t--columns [columns]".green());
+    println!("\t\tColumns for csv and tsv output, defaults to quickid,date,added,text");
+    println!("\t\tAlso available: updated, tags, kind, status, signifier");
+
+    println!("{}", "\t--on, --date [date]".green());
+    println!("\t\tFile the added entry under the given date instead of today");
+
+    println!("{}", "\t--since [date]".green());
+    println!("\t\tList or find entries from the given date, until today unless --until is set");
+
+    println!("{}", "\t--until [date]".green());
+    println!("\t\tList or find entries up to and including the given date");
+
+    println!("{}", "\t-t, --tag [tag]".green());
+    println!("\t\tOnly list entries with the #tag or +context, a bare name is a #tag");
+
+    println!("{}", "\t--color [auto, always, never]".green());
+    println!("\t\tWhen to use colors in the output, defaults to auto");
+
     println!("{}", "\t-f, --format [format string]".green());
     println!("\t\tFormat string for in the markdown output, defaults to '* {{bullet}}'");
-    println!("\t\t\t {{quickid}} - the quickid of the bullet");
-    println!("\t\t\t {{bullet}} - the text of the bullet");
-    println!("\t\t\t {{date}} - the date of the bullet - ISO 8601");
-    println!("\t\t\t {{added}} - the date & time the bullet was added - ISO 8601");
-    println!("\t\t\t {{yyyy}} - the year added");
-    println!("\t\t\t {{mm}} - the month added");
-    println!("\t\t\t {{dd}} - the day added");
-    println!("\t\t\t {{HH}} - the hour added");
-    println!("\t\t\t {{MM}} - the minute added");
-    println!("\t\t\t {{SS}} - the second added");
+    println!("\t\t@name uses a saved or built in template, see the template action");
+    for (field, description) in template::FIELDS.iter() {
+        println!("\t\t\t {{{}}} - {}", field, description);
+    }
+    println!("\t\t\t {{added:%H:%M}} - a date field with a strftime format");
+    println!("\t\t\t {{text:40}} - padded or cut to a width");
+    println!("\t\t\t {{tags|join(\", \")}} - the tags joined, also |upper and |lower");
+    println!("\t\t\t {{?tags}}...{{/tags}} - only shown when the field is not empty");
+    println!("\t\t\t {{{{ and }}}} - a literal {{ and }}");
 }
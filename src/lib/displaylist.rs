@@ -1,103 +1,277 @@
 use crate::bargs;
 use crate::bargs::BJournRunner;
+use crate::config;
+use crate::dates;
 use crate::db;
+use crate::formatters;
+use crate::tags;
+use crate::template;
 use colored::Colorize;
 use serde_json::json;
 use std::io::IsTerminal;
 
-fn format_line(format: &str, bullet: db::BItem) -> String {
-    let mut content = format.to_string();
-
-    let bdate = chrono::NaiveDate::parse_from_str(&bullet.list_date, "%Y-%m-%d").unwrap();
-    let added_date =
-        chrono::NaiveDateTime::parse_from_str(&bullet.added, "%Y-%m-%d %H:%M:%S").unwrap();
-
-    // replace for all the values
-    content = content.replace("{quickid}", &bullet.quickid);
-    content = content.replace("{bullet}", &bullet.text);
-    content = content.replace("{date}", &bdate.format("%Y-%m-%d").to_string());
-    content = content.replace(
-        "{{added}}",
-        &added_date.format("%Y-%m-%d %H:%M:%S").to_string(),
-    );
-    content = content.replace("{yyyy}", &added_date.format("%Y").to_string());
-    content = content.replace("{mm}", &added_date.format("%m").to_string());
-    content = content.replace("{dd}", &added_date.format("%d").to_string());
-    content = content.replace("{HH}", &added_date.format("%H").to_string());
-    content = content.replace("{MM}", &added_date.format("%M").to_string());
-    content = content.replace("{SS}", &added_date.format("%S").to_string());
-
-    content
+fn displaytrash_md(list: Vec<db::BItem>) {
+    for bullet in list {
+        let deleted = bullet.deleted_at.unwrap_or_default();
+        if std::io::stdout().is_terminal() {
+            println!(
+                "{} {} {}: {} {}",
+                "*".bold(),
+                bullet.list_date.dimmed(),
+                bullet.quickid.magenta(),
+                bullet.text,
+                format!("(removed {})", deleted).dimmed()
+            );
+        } else {
+            // for piping output
+            println!(
+                "* {} {}: {} (removed {})",
+                bullet.list_date, bullet.quickid, bullet.text, deleted
+            );
+        }
+    }
 }
 
-fn displaylist_md_row_terminal(itm: db::BItem, format: &String) {
-    if format == "{default}" {
-        println!("{} {}: {}", "*".bold(), itm.quickid.magenta(), itm.text);
-    } else {
-        println!("{}", format_line(format, itm));
+fn displaytrash_json(list: Vec<db::BItem>) {
+    let mut items = Vec::new();
+    for bullet in list {
+        let deleted = bullet.deleted_at.clone();
+        let mut item = formatters::bullet_json(&bullet);
+        item["deleted_at"] = json!(deleted);
+        items.push(item);
     }
+
+    println!("{}", json!(items));
 }
 
-fn displaylist_md_row(itm: db::BItem, format: &String) {
-    if format == "{default}" {
-        println!("* {}", itm.text);
-    } else {
-        println!("{}", format_line(format, itm));
+// list the bullets in the trash
+pub fn displaytrash(args: &bargs::BArgs) {
+    let format = match formatters::summary_format(
+        args.flag_arg("output").as_deref(),
+        config::get().output.as_deref(),
+    ) {
+        Ok(f) => f,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(exitcode::USAGE);
+        }
+    };
+
+    let list = match db::list_trash() {
+        Ok(l) => l,
+        Err(e) => {
+            eprintln!("Error listing the trash: {}", e);
+            std::process::exit(exitcode::IOERR);
+        }
+    };
+
+    match format {
+        formatters::SummaryFormat::Markdown => displaytrash_md(list),
+        formatters::SummaryFormat::Json => displaytrash_json(list),
     }
 }
 
-fn displaylist_md(list: Vec<db::BItem>, format: String) {
-    for bullet in list {
+fn displaytags_md(counts: Vec<(String, i64)>) {
+    for (tag, count) in counts {
         if std::io::stdout().is_terminal() {
-            displaylist_md_row_terminal(bullet, &format);
+            println!(
+                "{} {} {}",
+                "*".bold(),
+                tag.cyan(),
+                format!("({})", count).dimmed()
+            );
         } else {
             // for piping output
-            displaylist_md_row(bullet, &format);
+            println!("* {} ({})", tag, count);
         }
     }
 }
 
-fn displaylist_json(list: Vec<db::BItem>) {
+fn displaytags_json(counts: Vec<(String, i64)>) {
     let mut items = Vec::new();
-    for bullet in list {
+    for (tag, count) in counts {
         items.push(json!({
-            "quickid": bullet.quickid,
-            "bullet": bullet.text,
-            "date": bullet.list_date,
-            "added": bullet.added,
+            "tag": tag,
+            "count": count,
         }));
     }
 
     println!("{}", json!(items));
 }
 
+// list the tags with how many bullets use them
+pub fn displaytags(args: &bargs::BArgs) {
+    let format = match formatters::summary_format(
+        args.flag_arg("output").as_deref(),
+        config::get().output.as_deref(),
+    ) {
+        Ok(f) => f,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(exitcode::USAGE);
+        }
+    };
+
+    let counts = match db::tag_counts() {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Error listing tags: {}", e);
+            std::process::exit(exitcode::IOERR);
+        }
+    };
+
+    match format {
+        formatters::SummaryFormat::Markdown => displaytags_md(counts),
+        formatters::SummaryFormat::Json => displaytags_json(counts),
+    }
+}
+
+// works out the range of days to list, a range can be given as the input
+// e.g. 2025-01-01..2025-01-07, last week or with the --since and --until flags
+fn list_range(args: &bargs::BArgs) -> Result<dates::DateRange, String> {
+    let today = dates::today();
+
+    let mut range = match args.input {
+        Some(ref d) => match d.split_once("..") {
+            Some((s, e)) => dates::DateRange {
+                start: dates::resolve(s)?.start,
+                end: dates::resolve(e)?.end,
+            },
+            None => dates::resolve(d)?,
+        },
+        None => dates::resolve("today")?,
+    };
+
+    if let Some(since) = args.flag_arg("since") {
+        range.start = dates::resolve(&since)?.start;
+        if args.input.is_none() {
+            range.end = today;
+        }
+    }
+
+    if let Some(until) = args.flag_arg("until") {
+        range.end = dates::resolve(&until)?.end;
+        if args.input.is_none() && args.flag_arg("since").is_none() {
+            range.start = chrono::NaiveDate::from_ymd_opt(1, 1, 1).unwrap();
+        }
+    }
+
+    if range.start > range.end {
+        return Err(format!(
+            "The start date {} is after the end date {}",
+            range.start_str(),
+            range.end_str()
+        ));
+    }
+
+    Ok(range)
+}
+
 pub fn displaylist(args: &bargs::BArgs) {
-    let format = match args.flag_arg("output") {
+    let format = match args.flag_arg("output").or(config::get().output.clone()) {
         Some(val) => val,
         None => "md".to_string(),
     };
 
-    let line_format: String = match args.flag_arg("format") {
+    let line_format: String = match args.flag_arg("format").or(config::get().format.clone()) {
         Some(val) => val,
         None => "{default}".to_string(),
     };
+    // -f @name uses a saved template
+    let line_format = match template::resolve(&line_format) {
+        Ok(f) => f,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(exitcode::USAGE);
+        }
+    };
+
+    let columns = match args.flag_arg("columns") {
+        Some(val) => val,
+        None => formatters::DELIMITED_DEFAULT.to_string(),
+    };
 
     // read in the date as the second arg (if blank use today)
-    let date = match args.input {
-        Some(ref d) => d,
-        None => &chrono::Local::now().format("%Y-%m-%d").to_string(),
+    let range = match list_range(args) {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(exitcode::USAGE);
+        }
+    };
+
+    let options = formatters::FormatOptions {
+        line_format,
+        columns,
+        multi_day: !range.is_single_day(),
+        terminal: std::io::stdout().is_terminal(),
+    };
+
+    let mut formatter = match formatters::formatter(&format, &options) {
+        Ok(f) => f,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(exitcode::USAGE);
+        }
     };
 
-    let list = db::list_bullets(date);
-    if let Err(e) = list {
+    let tag = args.flag_arg("tag").map(|t| tags::normalize_tag(&t));
+
+    let order = db::ListOrder {
+        sort: match args.flag_arg("sort") {
+            Some(sort) if db::SORTS.contains(&sort.as_str()) => sort,
+            Some(sort) => {
+                eprintln!(
+                    "Error: unknown sort {}, use one of {}",
+                    sort,
+                    db::SORTS.join(", ")
+                );
+                std::process::exit(exitcode::USAGE);
+            }
+            None => db::SORTS[0].to_string(),
+        },
+        reverse: args.has_flag("reverse"),
+    };
+
+    let mut stdout = std::io::stdout().lock();
+    let mut writer =
+        match formatters::Writer::new(formatter.as_mut(), &mut stdout, options.multi_day) {
+            Ok(w) => w,
+            Err(e) => {
+                eprintln!("Error writing the list: {}", e);
+                std::process::exit(exitcode::IOERR);
+            }
+        };
+
+    // rows are written as they are read, stopping at the first write error
+    let mut write_error = None;
+    let listed = db::each_bullet_range(
+        &range.start_str(),
+        &range.end_str(),
+        tag.as_deref(),
+        &order,
+        |bullet| match writer.row(&bullet) {
+            Ok(()) => true,
+            Err(e) => {
+                write_error = Some(e);
+                false
+            }
+        },
+    );
+    if let Err(e) = listed {
         eprintln!("Error listing bullets: {}", e);
         std::process::exit(exitcode::IOERR);
     }
 
-    match format.as_str() {
-        "md" => displaylist_md(list.unwrap(), line_format),
-        "markdown" => displaylist_md(list.unwrap(), line_format),
-        "json" => displaylist_json(list.unwrap()),
-        _ => eprintln!("Unknown format: {}", format),
+    let finished = match write_error {
+        Some(e) => Err(e),
+        None => writer.finish(),
+    };
+    if let Err(e) = finished {
+        // a closed pipe e.g. piping to head is not an error
+        if e.kind() != std::io::ErrorKind::BrokenPipe {
+            eprintln!("Error writing the list: {}", e);
+            std::process::exit(exitcode::IOERR);
+        }
     }
 }
@@ -0,0 +1,306 @@
+// Weekly and monthly digests of the bullets, grouped by day and by tag
+use crate::bargs;
+use crate::bargs::BJournRunner;
+use crate::config;
+use crate::dates;
+use crate::db;
+use crate::formatters;
+use crate::tags;
+use crate::template;
+use colored::Colorize;
+use serde_json::json;
+use std::io::{IsTerminal, Write};
+
+pub struct Report {
+    pub range: dates::DateRange,
+    pub days: Vec<(String, Vec<db::BItem>)>,
+    pub tags: Vec<(String, Vec<db::BItem>)>,
+    pub open_tasks: Vec<db::BItem>,
+    pub total: usize,
+}
+
+// Groups the bullets, which are ordered by day, the most used tags come first
+pub fn build_report(range: dates::DateRange, bullets: Vec<db::BItem>) -> Report {
+    let mut days: Vec<(String, Vec<db::BItem>)> = Vec::new();
+    let mut tags: Vec<(String, Vec<db::BItem>)> = Vec::new();
+    let mut open_tasks = Vec::new();
+    let total = bullets.len();
+
+    for bullet in bullets {
+        for tag in &bullet.tags {
+            match tags.iter_mut().find(|(t, _)| t == tag) {
+                Some((_, items)) => items.push(bullet.clone()),
+                None => tags.push((tag.clone(), vec![bullet.clone()])),
+            }
+        }
+
+        if bullet.kind == "task" && bullet.status == "open" {
+            open_tasks.push(bullet.clone());
+        }
+
+        match days.last_mut() {
+            Some((date, items)) if *date == bullet.list_date => items.push(bullet),
+            _ => days.push((bullet.list_date.clone(), vec![bullet])),
+        }
+    }
+
+    tags.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then(a.0.cmp(&b.0)));
+
+    Report {
+        range,
+        days,
+        tags,
+        open_tasks,
+        total,
+    }
+}
+
+fn report_json(report: &Report) -> serde_json::Value {
+    let bullets = |items: &Vec<db::BItem>| -> Vec<serde_json::Value> {
+        items.iter().map(formatters::bullet_json).collect()
+    };
+
+    json!({
+        "start": report.range.start_str(),
+        "end": report.range.end_str(),
+        "total": report.total,
+        "days": report.days.iter().map(|(date, items)| json!({
+            "date": date,
+            "count": items.len(),
+            "bullets": bullets(items),
+        })).collect::<Vec<_>>(),
+        "tags": report.tags.iter().map(|(tag, items)| json!({
+            "tag": tag,
+            "count": items.len(),
+            "bullets": bullets(items),
+        })).collect::<Vec<_>>(),
+        "open_tasks": bullets(&report.open_tasks),
+    })
+}
+
+fn heading(out: &mut dyn Write, level: usize, text: &str, terminal: bool) -> std::io::Result<()> {
+    if terminal {
+        writeln!(out, "{}", text.bold().underline())?;
+    } else {
+        writeln!(out, "{} {}", "#".repeat(level), text)?;
+    }
+    writeln!(out)
+}
+
+// the rows of each section go through the markdown formatter, so -f works as it does for list
+fn section(
+    out: &mut dyn Write,
+    formatter: &mut dyn formatters::OutputFormatter,
+    items: &[db::BItem],
+) -> std::io::Result<()> {
+    let mut writer = formatters::Writer::new(formatter, out, false)?;
+    for item in items {
+        writer.row(item)?;
+    }
+    writer.finish()?;
+    writeln!(out)
+}
+
+fn report_md(
+    out: &mut dyn Write,
+    report: &Report,
+    title: &str,
+    terminal: bool,
+    formatter: &mut dyn formatters::OutputFormatter,
+) -> std::io::Result<()> {
+    heading(
+        out,
+        1,
+        &format!(
+            "{} {} to {}",
+            title,
+            report.range.start_str(),
+            report.range.end_str()
+        ),
+        terminal,
+    )?;
+    writeln!(
+        out,
+        "{} entries over {} days, {} open tasks",
+        report.total,
+        report.days.len(),
+        report.open_tasks.len()
+    )?;
+    writeln!(out)?;
+
+    heading(out, 2, "By day", terminal)?;
+    for (date, items) in &report.days {
+        heading(out, 3, &format!("{} ({})", date, items.len()), terminal)?;
+        section(out, formatter, items)?;
+    }
+
+    if !report.tags.is_empty() {
+        heading(out, 2, "By tag", terminal)?;
+        for (tag, items) in &report.tags {
+            heading(out, 3, &format!("{} ({})", tag, items.len()), terminal)?;
+            section(out, formatter, items)?;
+        }
+    }
+
+    if !report.open_tasks.is_empty() {
+        heading(out, 2, "Open tasks", terminal)?;
+        section(out, formatter, &report.open_tasks)?;
+    }
+
+    Ok(())
+}
+
+// the week or month containing the input day, this week when neither is given
+fn report_range(args: &bargs::BArgs) -> Result<(&'static str, dates::DateRange), String> {
+    let day = match args.input {
+        Some(ref d) => dates::resolve(d)?.start,
+        None => dates::today(),
+    };
+
+    if args.has_flag("month") {
+        Ok(("Month", dates::month_range(day)))
+    } else {
+        Ok(("Week", dates::week_range(day, dates::week_start())))
+    }
+}
+
+pub fn displayreport(args: &bargs::BArgs) {
+    let format = match formatters::summary_format(
+        args.flag_arg("output").as_deref(),
+        config::get().output.as_deref(),
+    ) {
+        Ok(f) => f,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(exitcode::USAGE);
+        }
+    };
+
+    let (title, range) = match report_range(args) {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(exitcode::USAGE);
+        }
+    };
+
+    let line_format: String = match args.flag_arg("format").or(config::get().format.clone()) {
+        Some(val) => val,
+        None => "{default}".to_string(),
+    };
+    let line_format = match template::resolve(&line_format) {
+        Ok(f) => f,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(exitcode::USAGE);
+        }
+    };
+
+    let tag = args.flag_arg("tag").map(|t| tags::normalize_tag(&t));
+    let bullets = match db::list_bullets_range(&range.start_str(), &range.end_str(), tag.as_deref())
+    {
+        Ok(b) => b,
+        Err(e) => {
+            eprintln!("Error listing bullets: {}", e);
+            std::process::exit(exitcode::IOERR);
+        }
+    };
+    let report = build_report(range, bullets);
+
+    let mut stdout = std::io::stdout().lock();
+    let written = match format {
+        formatters::SummaryFormat::Markdown => {
+            let options = formatters::FormatOptions {
+                line_format,
+                columns: formatters::DELIMITED_DEFAULT.to_string(),
+                multi_day: false,
+                terminal: std::io::stdout().is_terminal(),
+            };
+            // the line format is parsed once for all the sections
+            let mut formatter = match formatters::formatter("md", &options) {
+                Ok(f) => f,
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(exitcode::USAGE);
+                }
+            };
+            report_md(
+                &mut stdout,
+                &report,
+                title,
+                options.terminal,
+                formatter.as_mut(),
+            )
+        }
+        formatters::SummaryFormat::Json => writeln!(stdout, "{}", report_json(&report)),
+    };
+
+    if let Err(e) = written {
+        if e.kind() != std::io::ErrorKind::BrokenPipe {
+            eprintln!("Error writing the report: {}", e);
+            std::process::exit(exitcode::IOERR);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bullet(date: &str, text: &str, kind: &str, tags: &[&str]) -> db::BItem {
+        db::BItem {
+            id: 1,
+            quickid: "beWLHOFj".to_string(),
+            added: format!("{} 08:12:45", date),
+            list_date: date.to_string(),
+            text: text.to_string(),
+            updated: None,
+            deleted_at: None,
+            kind: kind.to_string(),
+            status: "open".to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_build_report() {
+        let range = dates::week_range(
+            chrono::NaiveDate::from_ymd_opt(2025, 1, 15).unwrap(),
+            chrono::Weekday::Mon,
+        );
+        let report = build_report(
+            range,
+            vec![
+                bullet("2025-01-13", "standup", "note", &["#work"]),
+                bullet("2025-01-13", "fix the build", "task", &["#work", "+ci"]),
+                bullet("2025-01-15", "lunch", "event", &[]),
+                bullet("2025-01-16", "release", "task", &["+ci"]),
+                bullet("2025-01-16", "review", "note", &["#work"]),
+            ],
+        );
+
+        assert_eq!(report.total, 5);
+        let days: Vec<(&str, usize)> = report
+            .days
+            .iter()
+            .map(|(d, b)| (d.as_str(), b.len()))
+            .collect();
+        assert_eq!(
+            days,
+            vec![("2025-01-13", 2), ("2025-01-15", 1), ("2025-01-16", 2)]
+        );
+        let tags: Vec<(&str, usize)> = report
+            .tags
+            .iter()
+            .map(|(t, b)| (t.as_str(), b.len()))
+            .collect();
+        assert_eq!(tags, vec![("#work", 3), ("+ci", 2)]);
+        assert_eq!(report.open_tasks.len(), 2);
+
+        let value = report_json(&report);
+        assert_eq!(value["start"], "2025-01-13");
+        assert_eq!(value["end"], "2025-01-19");
+        assert_eq!(value["days"][0]["count"], 2);
+        assert_eq!(value["open_tasks"][1]["bullet"], "release");
+    }
+}
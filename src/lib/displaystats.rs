@@ -0,0 +1,301 @@
+// Statistics from the added timestamps: totals, streaks and the busiest hour
+use crate::bargs;
+use crate::bargs::BJournRunner;
+use crate::config;
+use crate::dates;
+use crate::db;
+use crate::formatters;
+use chrono::{Days, NaiveDate, NaiveDateTime, Timelike};
+use colored::Colorize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::io::IsTerminal;
+
+// how many days the sparkline covers unless --days is given
+static DEFAULT_DAYS: u64 = 30;
+// the most days --days can cover, ten years
+static MAX_DAYS: u64 = 3660;
+// how many of the most used tags are shown
+static TOP_TAGS: usize = 5;
+
+static SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+#[derive(Debug, PartialEq)]
+pub struct Stats {
+    pub total: usize,
+    // bullets added each day over the last days, oldest first
+    pub per_day: Vec<(NaiveDate, usize)>,
+    pub current_streak: usize,
+    pub longest_streak: usize,
+    // the hour with the most bullets added and how many
+    pub busiest_hour: Option<(u32, usize)>,
+}
+
+// Works out the stats from the added timestamps, which are ordered oldest first
+pub fn build_stats(added: &[NaiveDateTime], today: NaiveDate, days: u64) -> Stats {
+    let mut logged: Vec<NaiveDate> = added.iter().map(|a| a.date()).collect();
+    logged.dedup();
+
+    let mut counts: HashMap<NaiveDate, usize> = HashMap::new();
+    for a in added {
+        *counts.entry(a.date()).or_insert(0) += 1;
+    }
+
+    let mut per_day = Vec::new();
+    let mut day = today
+        .checked_sub_days(Days::new(days.saturating_sub(1)))
+        .unwrap_or(NaiveDate::MIN);
+    while day <= today {
+        per_day.push((day, counts.get(&day).copied().unwrap_or(0)));
+        day = match day.succ_opt() {
+            Some(d) => d,
+            None => break,
+        };
+    }
+
+    let mut longest_streak = 0;
+    let mut streak = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for day in &logged {
+        streak = match previous {
+            Some(p) if p + Days::new(1) == *day => streak + 1,
+            _ => 1,
+        };
+        longest_streak = longest_streak.max(streak);
+        previous = Some(*day);
+    }
+
+    // the current streak still counts when nothing has been added yet today
+    let current_streak = match logged.last() {
+        Some(last) if *last == today || *last + Days::new(1) == today => streak,
+        _ => 0,
+    };
+
+    let mut hours = [0usize; 24];
+    for a in added {
+        hours[a.hour() as usize] += 1;
+    }
+    let busiest_hour = hours
+        .iter()
+        .enumerate()
+        .filter(|(_, count)| **count > 0)
+        // the earliest hour wins a tie
+        .max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(&a.0)))
+        .map(|(hour, count)| (hour as u32, *count));
+
+    Stats {
+        total: added.len(),
+        per_day,
+        current_streak,
+        longest_streak,
+        busiest_hour,
+    }
+}
+
+// a bar for each count, scaled to the largest count
+pub fn sparkline(counts: &[usize]) -> String {
+    let max = counts.iter().copied().max().unwrap_or(0);
+    counts
+        .iter()
+        .map(|c| match max {
+            0 => SPARKS[0],
+            _ => SPARKS[c * (SPARKS.len() - 1) / max],
+        })
+        .collect()
+}
+
+fn displaystats_md(stats: &Stats, tags: &[(String, i64)]) {
+    let terminal = std::io::stdout().is_terminal();
+    let counts: Vec<usize> = stats.per_day.iter().map(|(_, c)| *c).collect();
+    let first = stats.per_day.first().map(|(d, _)| d.to_string());
+    let last = stats.per_day.last().map(|(d, _)| d.to_string());
+
+    let label = |l: &str| {
+        if terminal {
+            format!("{}", l.bold())
+        } else {
+            l.to_string()
+        }
+    };
+
+    println!("{} {}", label("Total:"), stats.total);
+    println!(
+        "{} {} {} to {} ({} added)",
+        label("Per day:"),
+        if terminal {
+            sparkline(&counts).cyan().to_string()
+        } else {
+            sparkline(&counts)
+        },
+        first.unwrap_or_default(),
+        last.unwrap_or_default(),
+        counts.iter().sum::<usize>()
+    );
+    println!(
+        "{} {} days, longest {} days",
+        label("Streak:"),
+        stats.current_streak,
+        stats.longest_streak
+    );
+    match stats.busiest_hour {
+        Some((hour, count)) => println!(
+            "{} {:02}:00-{:02}:59 ({} added)",
+            label("Busiest hour:"),
+            hour,
+            hour,
+            count
+        ),
+        None => println!("{} none", label("Busiest hour:")),
+    }
+
+    if !tags.is_empty() {
+        let top: Vec<String> = tags
+            .iter()
+            .map(|(tag, count)| format!("{} ({})", tag, count))
+            .collect();
+        println!("{} {}", label("Top tags:"), top.join(", "));
+    }
+}
+
+fn displaystats_json(stats: &Stats, tags: &[(String, i64)]) {
+    let per_day: Vec<serde_json::Value> = stats
+        .per_day
+        .iter()
+        .map(|(date, count)| json!({"date": date.to_string(), "count": count}))
+        .collect();
+    let top_tags: Vec<serde_json::Value> = tags
+        .iter()
+        .map(|(tag, count)| json!({"tag": tag, "count": count}))
+        .collect();
+
+    println!(
+        "{}",
+        json!({
+            "total": stats.total,
+            "per_day": per_day,
+            "current_streak": stats.current_streak,
+            "longest_streak": stats.longest_streak,
+            "busiest_hour": stats.busiest_hour.map(|(hour, count)| json!({"hour": hour, "count": count})),
+            "top_tags": top_tags,
+        })
+    );
+}
+
+pub fn displaystats(args: &bargs::BArgs) {
+    let format = match formatters::summary_format(
+        args.flag_arg("output").as_deref(),
+        config::get().output.as_deref(),
+    ) {
+        Ok(f) => f,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(exitcode::USAGE);
+        }
+    };
+
+    let days = match args.flag_arg("days") {
+        Some(val) => match val.parse::<u64>() {
+            Ok(d) if d > 0 && d <= MAX_DAYS => d,
+            _ => {
+                eprintln!("Error: --days must be a number from 1 to {}", MAX_DAYS);
+                std::process::exit(exitcode::USAGE);
+            }
+        },
+        None => DEFAULT_DAYS,
+    };
+
+    let added: Vec<NaiveDateTime> = match db::added_times() {
+        Ok(a) => a
+            .iter()
+            .filter_map(|a| NaiveDateTime::parse_from_str(a, "%Y-%m-%d %H:%M:%S").ok())
+            .collect(),
+        Err(e) => {
+            eprintln!("Error reading bullets: {}", e);
+            std::process::exit(exitcode::IOERR);
+        }
+    };
+
+    let mut tags = match db::tag_counts() {
+        Ok(t) => t,
+        Err(e) => {
+            eprintln!("Error listing tags: {}", e);
+            std::process::exit(exitcode::IOERR);
+        }
+    };
+    tags.truncate(TOP_TAGS);
+
+    let stats = build_stats(&added, dates::today(), days);
+
+    match format {
+        formatters::SummaryFormat::Markdown => displaystats_md(&stats, &tags),
+        formatters::SummaryFormat::Json => displaystats_json(&stats, &tags),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    fn d(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(sparkline(&[0, 1, 7]), "▁▂█");
+        assert_eq!(sparkline(&[0, 0]), "▁▁");
+        assert_eq!(sparkline(&[]), "");
+    }
+
+    #[test]
+    fn test_build_stats() {
+        let added = [
+            at("2025-01-01 09:00:00"),
+            at("2025-01-02 09:30:00"),
+            at("2025-01-03 14:00:00"),
+            at("2025-01-10 09:15:00"),
+            at("2025-01-14 14:10:00"),
+            at("2025-01-15 20:00:00"),
+        ];
+
+        let stats = build_stats(&added, d("2025-01-15"), 3);
+        assert_eq!(stats.total, 6);
+        assert_eq!(
+            stats.per_day,
+            vec![
+                (d("2025-01-13"), 0),
+                (d("2025-01-14"), 1),
+                (d("2025-01-15"), 1)
+            ]
+        );
+        assert_eq!(stats.current_streak, 2);
+        assert_eq!(stats.longest_streak, 3);
+        assert_eq!(stats.busiest_hour, Some((9, 3)));
+
+        // nothing yet today keeps the streak from yesterday
+        assert_eq!(build_stats(&added, d("2025-01-16"), 1).current_streak, 2);
+        assert_eq!(build_stats(&added, d("2025-01-17"), 1).current_streak, 0);
+    }
+
+    #[test]
+    fn test_build_stats_empty() {
+        let stats = build_stats(&[], d("2025-01-15"), 2);
+        assert_eq!(stats.total, 0);
+        assert_eq!(stats.current_streak, 0);
+        assert_eq!(stats.longest_streak, 0);
+        assert_eq!(stats.busiest_hour, None);
+        assert_eq!(stats.per_day.len(), 2);
+    }
+
+    #[test]
+    fn test_build_stats_before_the_first_date() {
+        // more days than there are before today stops at the first date instead of panicking
+        let stats = build_stats(&[], NaiveDate::MIN + Days::new(2), 99_999_999_999);
+        assert_eq!(stats.per_day.len(), 3);
+        assert_eq!(stats.per_day[0].0, NaiveDate::MIN);
+    }
+}
@@ -0,0 +1,48 @@
+// Opens the users editor to change a piece of text
+use nanoid::nanoid;
+use std::io::{Error, Result, Write};
+use std::process::Command;
+
+// $VISUAL then $EDITOR, falling back to vi
+fn editor_command() -> String {
+    for var in ["VISUAL", "EDITOR"] {
+        if let Ok(val) = std::env::var(var) {
+            if !val.trim().is_empty() {
+                return val;
+            }
+        }
+    }
+
+    "vi".to_string()
+}
+
+// Writes the text to a temp file, opens it in the editor and reads back the result
+pub fn edit_text(name: &str, text: &str) -> Result<String> {
+    // the temp dir is shared, so the name is random and an existing file or symlink is never
+    // written through, only the user can read it
+    let mut path = std::env::temp_dir();
+    path.push(format!("bjourn-{}-{}.md", name, nanoid!(12)));
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(&path)?;
+    writeln!(file, "{}", text)?;
+    drop(file);
+
+    // the editor can include arguments e.g. "code --wait"
+    let editor = editor_command();
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+
+    let status = Command::new(program).args(parts).arg(&path).status();
+    let content = std::fs::read_to_string(&path);
+    let _ = std::fs::remove_file(&path);
+
+    let status = status?;
+    if !status.success() {
+        return Err(Error::other(format!("{} exited with {}", program, status)));
+    }
+
+    Ok(content?.trim().to_string())
+}
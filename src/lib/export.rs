@@ -0,0 +1,343 @@
+// Export the journal to a markdown file for each day, e.g. for a git repo or an Obsidian vault
+use crate::bargs;
+use crate::bargs::BJournRunner;
+use crate::config;
+use crate::dates;
+use crate::db;
+use crate::formatters;
+use crate::template;
+use serde_json::json;
+use std::path::Path;
+
+// The markdown file for a day, YAML front matter followed by the bullets written by the md formatter
+pub fn day_markdown(
+    date: &str,
+    bullets: &[db::BItem],
+    formatter: &mut dyn formatters::OutputFormatter,
+) -> Result<String, String> {
+    let mut tags: Vec<String> = Vec::new();
+    for bullet in bullets {
+        for tag in &bullet.tags {
+            if !tags.contains(tag) {
+                tags.push(tag.clone());
+            }
+        }
+    }
+    tags.sort();
+
+    // tags are written as json strings, which are valid YAML and keep the # from starting a comment
+    let tag_list: Vec<String> = tags.iter().map(|t| json!(t).to_string()).collect();
+    let mut content = format!(
+        "---\ndate: {}\ntags: [{}]\ncount: {}\n---\n\n",
+        date,
+        tag_list.join(", "),
+        bullets.len()
+    );
+
+    let mut out: Vec<u8> = Vec::new();
+    let written = formatters::Writer::new(formatter, &mut out, false).and_then(|mut writer| {
+        for bullet in bullets {
+            writer.row(bullet)?;
+        }
+        writer.finish()
+    });
+    if let Err(e) = written {
+        return Err(e.to_string());
+    }
+    content.push_str(&String::from_utf8_lossy(&out));
+
+    Ok(content)
+}
+
+// Writes the file only when the content is different, returns true when it was written
+pub fn write_if_changed(path: &Path, content: &str) -> std::io::Result<bool> {
+    if let Ok(existing) = std::fs::read_to_string(path) {
+        if existing == content {
+            return Ok(false);
+        }
+    }
+
+    std::fs::write(path, content)?;
+    Ok(true)
+}
+
+// The day files in the range that weren't exported this time, the bullets for those days were
+// removed or moved. Only names like 2025-01-01.md count, other files in the directory are left alone
+pub fn stale_day_files(
+    dir: &Path,
+    start: &str,
+    end: &str,
+    exported: &[&str],
+) -> std::io::Result<Vec<std::path::PathBuf>> {
+    let mut stale = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let date = match path.file_name().and_then(|n| n.to_str()) {
+            Some(name) => match name.strip_suffix(".md") {
+                Some(date) => date.to_string(),
+                None => continue,
+            },
+            None => continue,
+        };
+        // the name has to be the date as the export writes it, not just parse as one
+        match chrono::NaiveDate::parse_from_str(&date, "%Y-%m-%d") {
+            Ok(d) if d.format("%Y-%m-%d").to_string() == date => {}
+            _ => continue,
+        }
+        if date.as_str() >= start && date.as_str() <= end && !exported.contains(&date.as_str()) {
+            stale.push(path);
+        }
+    }
+    stale.sort();
+
+    Ok(stale)
+}
+
+// A day file written by export starts with its front matter, anything else was written by hand
+fn is_exported(path: &Path, date: &str) -> bool {
+    match std::fs::read_to_string(path) {
+        Ok(content) => content.starts_with(&format!("---\ndate: {}\n", date)),
+        Err(_) => false,
+    }
+}
+
+fn export_range(args: &bargs::BArgs) -> Result<(String, String), String> {
+    let start = match args.flag_arg("since") {
+        Some(since) => dates::resolve(&since)?.start_str(),
+        None => "0001-01-01".to_string(),
+    };
+
+    let end = match args.flag_arg("until") {
+        Some(until) => dates::resolve(&until)?.end_str(),
+        None => "9999-12-31".to_string(),
+    };
+
+    Ok((start, end))
+}
+
+pub fn export(args: &bargs::BArgs) {
+    let dir = match args.flag_arg("dir") {
+        Some(d) => d,
+        None => {
+            eprintln!("Error: export requires a directory, e.g. --dir ./journal");
+            std::process::exit(exitcode::USAGE);
+        }
+    };
+
+    let (start, end) = match export_range(args) {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(exitcode::USAGE);
+        }
+    };
+
+    // the configured format is used for the bullets, the same as list
+    let line_format: String = match args.flag_arg("format").or(config::get().format.clone()) {
+        Some(val) => val,
+        None => "{default}".to_string(),
+    };
+    let options = formatters::FormatOptions {
+        line_format: match template::resolve(&line_format) {
+            Ok(f) => f,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(exitcode::USAGE);
+            }
+        },
+        columns: formatters::DELIMITED_DEFAULT.to_string(),
+        multi_day: false,
+        terminal: false,
+    };
+    // the line format is parsed once for all the days
+    let mut formatter = match formatters::formatter("md", &options) {
+        Ok(f) => f,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(exitcode::USAGE);
+        }
+    };
+
+    let bullets = match db::list_bullets_range(&start, &end, None) {
+        Ok(b) => b,
+        Err(e) => {
+            eprintln!("Error listing bullets: {}", e);
+            std::process::exit(exitcode::IOERR);
+        }
+    };
+
+    let dir = Path::new(&dir);
+    if let Err(e) = std::fs::create_dir_all(dir) {
+        eprintln!("Error creating {}: {}", dir.display(), e);
+        std::process::exit(exitcode::CANTCREAT);
+    }
+
+    let mut written = 0;
+    let mut unchanged = 0;
+    let mut exported: Vec<&str> = Vec::new();
+    for day in bullets.chunk_by(|a, b| a.list_date == b.list_date) {
+        let date = &day[0].list_date;
+        exported.push(date);
+        let content = match day_markdown(date, day, formatter.as_mut()) {
+            Ok(c) => c,
+            Err(e) => {
+                eprintln!("Error writing {}: {}", date, e);
+                std::process::exit(exitcode::IOERR);
+            }
+        };
+
+        let path = dir.join(format!("{}.md", date));
+        match write_if_changed(&path, &content) {
+            Ok(true) => written += 1,
+            Ok(false) => unchanged += 1,
+            Err(e) => {
+                eprintln!("Error writing {}: {}", path.display(), e);
+                std::process::exit(exitcode::IOERR);
+            }
+        }
+    }
+
+    // days in the range without bullets any more
+    let stale = match stale_day_files(dir, &start, &end, &exported) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("Error reading {}: {}", dir.display(), e);
+            std::process::exit(exitcode::IOERR);
+        }
+    };
+    let mut removed = 0;
+    for path in stale {
+        let date = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default();
+        if !is_exported(&path, date) {
+            println!(
+                "{} has no bullets but was not written by export, left in place",
+                path.display()
+            );
+            continue;
+        }
+        match std::fs::remove_file(&path) {
+            Ok(_) => removed += 1,
+            Err(e) => {
+                eprintln!("Error removing {}: {}", path.display(), e);
+                std::process::exit(exitcode::IOERR);
+            }
+        }
+    }
+
+    println!(
+        "Exported to {}: {} written, {} unchanged, {} removed",
+        dir.display(),
+        written,
+        unchanged,
+        removed
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bullet(text: &str, tags: &[&str]) -> db::BItem {
+        db::BItem {
+            id: 1,
+            quickid: "beWLHOFj".to_string(),
+            added: "2025-01-01 08:12:45".to_string(),
+            list_date: "2025-01-01".to_string(),
+            text: text.to_string(),
+            updated: None,
+            deleted_at: None,
+            kind: "note".to_string(),
+            status: "open".to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+        }
+    }
+
+    fn options(line_format: &str) -> formatters::FormatOptions {
+        formatters::FormatOptions {
+            line_format: line_format.to_string(),
+            columns: formatters::DELIMITED_DEFAULT.to_string(),
+            multi_day: false,
+            terminal: false,
+        }
+    }
+
+    #[test]
+    fn test_day_markdown() {
+        let bullets = [
+            bullet("breakfast with #family", &["#family"]),
+            bullet("fixed the #bjourn +home build", &["#bjourn", "+home"]),
+        ];
+
+        let mut formatter = formatters::formatter("md", &options("{default}")).unwrap();
+        assert_eq!(
+            day_markdown("2025-01-01", &bullets, formatter.as_mut()).unwrap(),
+            "---\ndate: 2025-01-01\ntags: [\"#bjourn\", \"#family\", \"+home\"]\ncount: 2\n---\n\n\
+             * breakfast with #family\n* fixed the #bjourn +home build\n"
+        );
+
+        // one formatter is used for every day
+        let mut formatter = formatters::formatter("md", &options("{HH}:{MM} {text}")).unwrap();
+        for date in ["2025-01-01", "2025-01-02"] {
+            assert!(day_markdown(date, &bullets, formatter.as_mut())
+                .unwrap()
+                .ends_with("08:12 breakfast with #family\n08:12 fixed the #bjourn +home build\n"));
+        }
+    }
+
+    #[test]
+    fn test_write_if_changed() {
+        let path = std::env::temp_dir().join(format!("bjourn-export-{}.md", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        assert!(write_if_changed(&path, "* one\n").unwrap());
+        assert!(!write_if_changed(&path, "* one\n").unwrap());
+        assert!(write_if_changed(&path, "* two\n").unwrap());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "* two\n");
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_stale_day_files() {
+        let dir = std::env::temp_dir().join(format!("bjourn-export-stale-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in [
+            "2024-12-31.md",
+            "2025-01-01.md",
+            "2025-01-02.md",
+            "2025-01-03.md",
+            "2025-02-30.md",
+            "2025-1-2.md",
+            "2025-01-02.txt",
+            "notes.md",
+        ] {
+            std::fs::write(dir.join(name), "---\ndate: 2025-01-02\n").unwrap();
+        }
+
+        // only the day files in the range that weren't exported again
+        let stale = stale_day_files(&dir, "2025-01-01", "2025-01-03", &["2025-01-01"]).unwrap();
+        assert_eq!(
+            stale,
+            vec![dir.join("2025-01-02.md"), dir.join("2025-01-03.md")]
+        );
+        assert!(stale_day_files(
+            &dir,
+            "2025-01-01",
+            "2025-01-03",
+            &["2025-01-01", "2025-01-02", "2025-01-03"]
+        )
+        .unwrap()
+        .is_empty());
+
+        // and only the ones export wrote are removed
+        assert!(is_exported(&dir.join("2025-01-02.md"), "2025-01-02"));
+        assert!(!is_exported(&dir.join("2025-01-03.md"), "2025-01-03"));
+        assert!(!is_exported(&dir.join("2025-01-04.md"), "2025-01-04"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,650 @@
+// Output formatters for lists of bullets, looked up by name from the registry
+use crate::db;
+use crate::template::Template;
+use colored::Colorize;
+use serde_json::json;
+use std::io::{Result, Write};
+
+pub struct FormatOptions {
+    // the --format string for markdown output, {default} when not set
+    pub line_format: String,
+    // the --columns for csv and tsv output
+    pub columns: String,
+    // bullets from more than one day are grouped under a heading for each day
+    pub multi_day: bool,
+    // writing to a terminal rather than a pipe
+    pub terminal: bool,
+}
+
+// A formatter is given the rows one at a time so a list can be streamed, begin and end
+// wrap the whole list and day is called before the first row of each day when multi_day
+pub trait OutputFormatter {
+    fn begin(&mut self, _out: &mut dyn Write) -> Result<()> {
+        Ok(())
+    }
+
+    fn day(&mut self, _out: &mut dyn Write, _date: &str) -> Result<()> {
+        Ok(())
+    }
+
+    fn row(&mut self, out: &mut dyn Write, bullet: &db::BItem) -> Result<()>;
+
+    fn end(&mut self, _out: &mut dyn Write) -> Result<()> {
+        Ok(())
+    }
+}
+
+type Constructor = fn(&FormatOptions) -> std::result::Result<Box<dyn OutputFormatter>, String>;
+
+// the registry of output formats, add new formats here
+static FORMATTERS: [(&str, Constructor); 8] = [
+    ("md", Markdown::create),
+    ("markdown", Markdown::create),
+    ("plain", Plain::create),
+    ("json", Json::create),
+    ("ndjson", NdJson::create),
+    ("csv", Delimited::create_csv),
+    ("tsv", Delimited::create_tsv),
+    ("html", Html::create),
+];
+
+pub fn available() -> Vec<&'static str> {
+    FORMATTERS.iter().map(|(name, _)| *name).collect()
+}
+
+fn unknown_format(name: &str, names: &[&str]) -> String {
+    format!("Unknown format {}, use one of {}", name, names.join(", "))
+}
+
+// Looks up a formatter by name, unknown names list the available formats in the error
+pub fn formatter(
+    name: &str,
+    options: &FormatOptions,
+) -> std::result::Result<Box<dyn OutputFormatter>, String> {
+    match FORMATTERS.iter().find(|(n, _)| *n == name) {
+        Some((_, create)) => create(options),
+        None => Err(unknown_format(name, &available())),
+    }
+}
+
+// Commands like tags, stats and cal only have their own markdown view and json
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SummaryFormat {
+    Markdown,
+    Json,
+}
+
+static SUMMARY_FORMATS: [(&str, SummaryFormat); 3] = [
+    ("md", SummaryFormat::Markdown),
+    ("markdown", SummaryFormat::Markdown),
+    ("json", SummaryFormat::Json),
+];
+
+fn find_summary_format(name: &str) -> Option<SummaryFormat> {
+    SUMMARY_FORMATS
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, format)| *format)
+}
+
+// The format for a command with only markdown and json, an unknown -o is an error but the
+// configured output only applies when the command supports it, e.g. csv is for lists
+pub fn summary_format(
+    name: Option<&str>,
+    configured: Option<&str>,
+) -> std::result::Result<SummaryFormat, String> {
+    match name {
+        Some(n) => find_summary_format(n)
+            .ok_or_else(|| unknown_format(n, &SUMMARY_FORMATS.map(|(n, _)| n))),
+        None => Ok(configured
+            .and_then(find_summary_format)
+            .unwrap_or(SummaryFormat::Markdown)),
+    }
+}
+
+// Feeds the bullets through the formatter, the bullets must be ordered by day
+pub struct Writer<'a> {
+    formatter: &'a mut dyn OutputFormatter,
+    out: &'a mut dyn Write,
+    multi_day: bool,
+    last_day: Option<String>,
+}
+
+impl<'a> Writer<'a> {
+    pub fn new(
+        formatter: &'a mut dyn OutputFormatter,
+        out: &'a mut dyn Write,
+        multi_day: bool,
+    ) -> Result<Self> {
+        formatter.begin(out)?;
+        Ok(Writer {
+            formatter,
+            out,
+            multi_day,
+            last_day: None,
+        })
+    }
+
+    pub fn row(&mut self, bullet: &db::BItem) -> Result<()> {
+        if self.multi_day && self.last_day.as_deref() != Some(bullet.list_date.as_str()) {
+            self.formatter.day(self.out, &bullet.list_date)?;
+            self.last_day = Some(bullet.list_date.clone());
+        }
+
+        self.formatter.row(self.out, bullet)
+    }
+
+    pub fn finish(self) -> Result<()> {
+        self.formatter.end(self.out)?;
+        self.out.flush()
+    }
+}
+
+// the bullet journal signifier for the kind and status of a bullet
+pub fn signifier(itm: &db::BItem) -> &'static str {
+    match (itm.kind.as_str(), itm.status.as_str()) {
+        ("task", "done") => "×",
+        ("task", "migrated") => ">",
+        ("task", _) => "•",
+        ("event", _) => "○",
+        _ => "–",
+    }
+}
+
+pub fn bullet_json(bullet: &db::BItem) -> serde_json::Value {
+    json!({
+        "quickid": bullet.quickid,
+        "bullet": bullet.text,
+        "date": bullet.list_date,
+        "added": bullet.added,
+        "updated": bullet.updated,
+        "tags": bullet.tags,
+        "kind": bullet.kind,
+        "status": bullet.status,
+    })
+}
+
+// Markdown list, with signifiers and colors in the terminal
+struct Markdown {
+    template: Option<Template>,
+    terminal: bool,
+    days: usize,
+}
+
+impl Markdown {
+    fn create(options: &FormatOptions) -> std::result::Result<Box<dyn OutputFormatter>, String> {
+        // the template is parsed once for the whole list
+        let template = match options.line_format.as_str() {
+            "{default}" => None,
+            f => Some(Template::parse(f)?),
+        };
+
+        Ok(Box::new(Markdown {
+            template,
+            terminal: options.terminal,
+            days: 0,
+        }))
+    }
+}
+
+impl OutputFormatter for Markdown {
+    fn day(&mut self, out: &mut dyn Write, date: &str) -> Result<()> {
+        if self.days > 0 {
+            writeln!(out)?;
+        }
+        self.days += 1;
+
+        if self.terminal {
+            writeln!(out, "{}", date.bold().underline())
+        } else {
+            writeln!(out, "## {}", date)?;
+            writeln!(out)
+        }
+    }
+
+    fn row(&mut self, out: &mut dyn Write, itm: &db::BItem) -> Result<()> {
+        if let Some(ref template) = self.template {
+            return writeln!(out, "{}", template.render(itm));
+        }
+
+        if self.terminal {
+            let text = match itm.status.as_str() {
+                "cancelled" => itm.text.strikethrough().dimmed(),
+                "done" | "migrated" => itm.text.dimmed(),
+                _ => itm.text.normal(),
+            };
+            return writeln!(
+                out,
+                "{} {}: {}",
+                signifier(itm).bold(),
+                itm.quickid.magenta(),
+                text
+            );
+        }
+
+        // for piping output, tasks are written as markdown checkboxes
+        match (itm.kind.as_str(), itm.status.as_str()) {
+            ("task", "done") => writeln!(out, "* [x] {}", itm.text),
+            ("task", "migrated") => writeln!(out, "* [>] {}", itm.text),
+            ("task", "cancelled") => writeln!(out, "* [ ] ~~{}~~", itm.text),
+            ("task", _) => writeln!(out, "* [ ] {}", itm.text),
+            _ => writeln!(out, "* {}", itm.text),
+        }
+    }
+}
+
+// Just the text of each bullet, one per line
+struct Plain {
+    days: usize,
+}
+
+impl Plain {
+    fn create(_options: &FormatOptions) -> std::result::Result<Box<dyn OutputFormatter>, String> {
+        Ok(Box::new(Plain { days: 0 }))
+    }
+}
+
+impl OutputFormatter for Plain {
+    fn day(&mut self, out: &mut dyn Write, date: &str) -> Result<()> {
+        if self.days > 0 {
+            writeln!(out)?;
+        }
+        self.days += 1;
+
+        writeln!(out, "{}", date)
+    }
+
+    fn row(&mut self, out: &mut dyn Write, bullet: &db::BItem) -> Result<()> {
+        writeln!(out, "{}", bullet.text)
+    }
+}
+
+// A json array of bullets, or of days with their bullets when listing more than one day
+struct Json {
+    multi_day: bool,
+    days: usize,
+    rows: usize,
+}
+
+impl Json {
+    fn create(options: &FormatOptions) -> std::result::Result<Box<dyn OutputFormatter>, String> {
+        Ok(Box::new(Json {
+            multi_day: options.multi_day,
+            days: 0,
+            rows: 0,
+        }))
+    }
+}
+
+impl OutputFormatter for Json {
+    fn begin(&mut self, out: &mut dyn Write) -> Result<()> {
+        write!(out, "[")
+    }
+
+    fn day(&mut self, out: &mut dyn Write, date: &str) -> Result<()> {
+        if self.days > 0 {
+            write!(out, "]}},")?;
+        }
+        self.days += 1;
+        self.rows = 0;
+
+        write!(out, "{{\"date\":{},\"bullets\":[", json!(date))
+    }
+
+    fn row(&mut self, out: &mut dyn Write, bullet: &db::BItem) -> Result<()> {
+        if self.rows > 0 {
+            write!(out, ",")?;
+        }
+        self.rows += 1;
+
+        write!(out, "{}", bullet_json(bullet))
+    }
+
+    fn end(&mut self, out: &mut dyn Write) -> Result<()> {
+        if self.multi_day && self.days > 0 {
+            write!(out, "]}}")?;
+        }
+
+        writeln!(out, "]")
+    }
+}
+
+// One json object per line
+struct NdJson;
+
+impl NdJson {
+    fn create(_options: &FormatOptions) -> std::result::Result<Box<dyn OutputFormatter>, String> {
+        Ok(Box::new(NdJson))
+    }
+}
+
+impl OutputFormatter for NdJson {
+    fn row(&mut self, out: &mut dyn Write, bullet: &db::BItem) -> Result<()> {
+        writeln!(out, "{}", bullet_json(bullet))
+    }
+}
+
+// the columns available for csv and tsv output, and the default selection
+pub static DELIMITED_COLUMNS: [&str; 9] = [
+    "quickid",
+    "date",
+    "added",
+    "text",
+    "updated",
+    "tags",
+    "kind",
+    "status",
+    "signifier",
+];
+pub static DELIMITED_DEFAULT: &str = "quickid,date,added,text";
+
+fn delimited_column(bullet: &db::BItem, column: &str) -> String {
+    match column {
+        "quickid" => bullet.quickid.clone(),
+        "date" => bullet.list_date.clone(),
+        "added" => bullet.added.clone(),
+        "text" => bullet.text.clone(),
+        "updated" => bullet.updated.clone().unwrap_or_default(),
+        "tags" => bullet.tags.join(" "),
+        "kind" => bullet.kind.clone(),
+        "status" => bullet.status.clone(),
+        "signifier" => signifier(bullet).to_string(),
+        _ => String::new(),
+    }
+}
+
+// quotes a field if it has the delimiter, quotes or line breaks, quotes are doubled
+fn delimited_field(value: &str, delimiter: char) -> String {
+    if value.contains([delimiter, '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// parses the --columns list e.g. date,text
+fn delimited_columns(columns: &str) -> std::result::Result<Vec<String>, String> {
+    let mut selected = Vec::new();
+    for column in columns
+        .split(',')
+        .map(|c| c.trim())
+        .filter(|c| !c.is_empty())
+    {
+        // bullet is the name used in json and --format
+        let column = if column == "bullet" { "text" } else { column };
+        if !DELIMITED_COLUMNS.contains(&column) {
+            return Err(format!(
+                "Unknown column {}, use any of {}",
+                column,
+                DELIMITED_COLUMNS.join(", ")
+            ));
+        }
+        selected.push(column.to_string());
+    }
+
+    if selected.is_empty() {
+        return Err("No columns given".to_string());
+    }
+
+    Ok(selected)
+}
+
+// csv or tsv with a header row
+struct Delimited {
+    delimiter: char,
+    columns: Vec<String>,
+}
+
+impl Delimited {
+    fn create_csv(
+        options: &FormatOptions,
+    ) -> std::result::Result<Box<dyn OutputFormatter>, String> {
+        Ok(Box::new(Delimited {
+            delimiter: ',',
+            columns: delimited_columns(&options.columns)?,
+        }))
+    }
+
+    fn create_tsv(
+        options: &FormatOptions,
+    ) -> std::result::Result<Box<dyn OutputFormatter>, String> {
+        Ok(Box::new(Delimited {
+            delimiter: '\t',
+            columns: delimited_columns(&options.columns)?,
+        }))
+    }
+}
+
+impl OutputFormatter for Delimited {
+    fn begin(&mut self, out: &mut dyn Write) -> Result<()> {
+        writeln!(out, "{}", self.columns.join(&self.delimiter.to_string()))
+    }
+
+    fn row(&mut self, out: &mut dyn Write, bullet: &db::BItem) -> Result<()> {
+        let row: Vec<String> = self
+            .columns
+            .iter()
+            .map(|c| delimited_field(&delimited_column(bullet, c), self.delimiter))
+            .collect();
+
+        writeln!(out, "{}", row.join(&self.delimiter.to_string()))
+    }
+}
+
+fn html_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// An html list, with a heading for each day
+struct Html {
+    open: bool,
+}
+
+impl Html {
+    fn create(_options: &FormatOptions) -> std::result::Result<Box<dyn OutputFormatter>, String> {
+        Ok(Box::new(Html { open: false }))
+    }
+}
+
+impl OutputFormatter for Html {
+    fn day(&mut self, out: &mut dyn Write, date: &str) -> Result<()> {
+        if self.open {
+            writeln!(out, "</ul>")?;
+            self.open = false;
+        }
+
+        writeln!(out, "<h2>{}</h2>", html_escape(date))
+    }
+
+    fn row(&mut self, out: &mut dyn Write, bullet: &db::BItem) -> Result<()> {
+        if !self.open {
+            writeln!(out, "<ul>")?;
+            self.open = true;
+        }
+
+        writeln!(
+            out,
+            "  <li class=\"{} {}\" data-quickid=\"{}\">{}</li>",
+            html_escape(&bullet.kind),
+            html_escape(&bullet.status),
+            html_escape(&bullet.quickid),
+            html_escape(&bullet.text).replace('\n', "<br>")
+        )
+    }
+
+    fn end(&mut self, out: &mut dyn Write) -> Result<()> {
+        if self.open {
+            writeln!(out, "</ul>")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bullet(date: &str, text: &str) -> db::BItem {
+        db::BItem {
+            id: 1,
+            quickid: "beWLHOFj".to_string(),
+            added: format!("{} 08:12:45", date),
+            list_date: date.to_string(),
+            text: text.to_string(),
+            updated: None,
+            deleted_at: None,
+            kind: "note".to_string(),
+            status: "open".to_string(),
+            tags: vec![],
+        }
+    }
+
+    fn render(name: &str, multi_day: bool, bullets: &[db::BItem]) -> String {
+        let options = FormatOptions {
+            line_format: "{default}".to_string(),
+            columns: DELIMITED_DEFAULT.to_string(),
+            multi_day,
+            terminal: false,
+        };
+        let mut out: Vec<u8> = Vec::new();
+        let mut formatter = formatter(name, &options).unwrap();
+        let mut writer = Writer::new(formatter.as_mut(), &mut out, multi_day).unwrap();
+        for b in bullets {
+            writer.row(b).unwrap();
+        }
+        writer.finish().unwrap();
+
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_unknown_format() {
+        let options = FormatOptions {
+            line_format: "{default}".to_string(),
+            columns: DELIMITED_DEFAULT.to_string(),
+            multi_day: false,
+            terminal: false,
+        };
+        let err = formatter("yaml", &options).err().unwrap();
+        assert!(err.contains("yaml"));
+        assert!(err.contains("ndjson"));
+    }
+
+    #[test]
+    fn test_summary_format() {
+        assert_eq!(summary_format(None, None), Ok(SummaryFormat::Markdown));
+        assert_eq!(
+            summary_format(Some("markdown"), None),
+            Ok(SummaryFormat::Markdown)
+        );
+        assert_eq!(summary_format(Some("json"), None), Ok(SummaryFormat::Json));
+        assert_eq!(
+            summary_format(Some("csv"), None),
+            Err("Unknown format csv, use one of md, markdown, json".to_string())
+        );
+
+        // a configured output the command doesn't have falls back to markdown
+        assert_eq!(summary_format(None, Some("json")), Ok(SummaryFormat::Json));
+        assert_eq!(
+            summary_format(None, Some("csv")),
+            Ok(SummaryFormat::Markdown)
+        );
+        assert_eq!(
+            summary_format(Some("md"), Some("json")),
+            Ok(SummaryFormat::Markdown)
+        );
+    }
+
+    #[test]
+    fn test_json_is_valid() {
+        let bullets = [
+            bullet("2025-01-01", "one"),
+            bullet("2025-01-01", "two"),
+            bullet("2025-01-02", "three"),
+        ];
+
+        let flat: serde_json::Value =
+            serde_json::from_str(&render("json", false, &bullets)).unwrap();
+        assert_eq!(flat.as_array().unwrap().len(), 3);
+
+        let days: serde_json::Value =
+            serde_json::from_str(&render("json", true, &bullets)).unwrap();
+        assert_eq!(days.as_array().unwrap().len(), 2);
+        assert_eq!(days[0]["date"], "2025-01-01");
+        assert_eq!(days[0]["bullets"].as_array().unwrap().len(), 2);
+        assert_eq!(days[1]["bullets"][0]["bullet"], "three");
+
+        let empty: serde_json::Value = serde_json::from_str(&render("json", true, &[])).unwrap();
+        assert_eq!(empty.as_array().unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_ndjson_and_plain() {
+        let bullets = [bullet("2025-01-01", "one"), bullet("2025-01-02", "two")];
+
+        let lines: Vec<String> = render("ndjson", true, &bullets)
+            .lines()
+            .map(|l| l.to_string())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        let second: serde_json::Value = serde_json::from_str(&lines[1]).unwrap();
+        assert_eq!(second["date"], "2025-01-02");
+
+        assert_eq!(render("plain", false, &bullets), "one\ntwo\n");
+    }
+
+    #[test]
+    fn test_markdown_days() {
+        let bullets = [bullet("2025-01-01", "one"), bullet("2025-01-02", "two")];
+        assert_eq!(
+            render("md", true, &bullets),
+            "## 2025-01-01\n\n* one\n\n## 2025-01-02\n\n* two\n"
+        );
+    }
+
+    #[test]
+    fn test_html_escapes() {
+        let bullets = [bullet("2025-01-01", "<b>bold</b> & more")];
+        assert_eq!(
+            render("html", false, &bullets),
+            "<ul>\n  <li class=\"note open\" data-quickid=\"beWLHOFj\">&lt;b&gt;bold&lt;/b&gt; &amp; more</li>\n</ul>\n"
+        );
+    }
+
+    #[test]
+    fn test_delimited_field() {
+        assert_eq!(delimited_field("plain text", ','), "plain text");
+        assert_eq!(delimited_field("a, b", ','), "\"a, b\"");
+        assert_eq!(delimited_field("a, b", '\t'), "a, b");
+        assert_eq!(delimited_field("a\tb", '\t'), "\"a\tb\"");
+        assert_eq!(delimited_field("say \"hi\"", ','), "\"say \"\"hi\"\"\"");
+        assert_eq!(
+            delimited_field("line one\nline two", ','),
+            "\"line one\nline two\""
+        );
+        assert_eq!(
+            delimited_field("line one\r\nline two", '\t'),
+            "\"line one\r\nline two\""
+        );
+    }
+
+    #[test]
+    fn test_delimited_columns() {
+        assert_eq!(
+            delimited_columns(DELIMITED_DEFAULT).unwrap(),
+            vec!["quickid", "date", "added", "text"]
+        );
+        assert_eq!(
+            delimited_columns("text, date").unwrap(),
+            vec!["text", "date"]
+        );
+        assert_eq!(delimited_columns("bullet").unwrap(), vec!["text"]);
+        assert!(delimited_columns("text,nope").is_err());
+        assert!(delimited_columns(",").is_err());
+    }
+}
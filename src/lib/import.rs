@@ -0,0 +1,471 @@
+// Import bullets from markdown lists, jrnl's plain text format and bjourn's own json
+use crate::bargs;
+use crate::bargs::BJournRunner;
+use crate::db;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use std::path::Path;
+
+// the file extensions read when importing a directory
+static EXTENSIONS: [&str; 4] = ["md", "markdown", "txt", "json"];
+
+fn new_bullet(date: NaiveDate, text: &str, kind: &str, status: &str) -> db::NewBullet {
+    db::NewBullet {
+        list_date: date.format("%Y-%m-%d").to_string(),
+        text: text.to_string(),
+        kind: kind.to_string(),
+        status: status.to_string(),
+        added: None,
+    }
+}
+
+// a YYYY-MM-DD at the start of the text
+fn leading_date(text: &str) -> Option<NaiveDate> {
+    text.get(..10)
+        .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+}
+
+// the first YYYY-MM-DD anywhere in the text, e.g. in a file name like journal-2025-01-01.md
+pub fn find_date(text: &str) -> Option<NaiveDate> {
+    text.char_indices()
+        .filter_map(|(i, _)| text.get(i..).and_then(leading_date))
+        .next()
+}
+
+// the text of a markdown list item with the kind and status from a checkbox
+fn markdown_item(item: &str) -> (String, &'static str, &'static str) {
+    let (kind, status, text) = match item.get(..3) {
+        Some("[ ]") => ("task", "open", &item[3..]),
+        Some("[x]") | Some("[X]") => ("task", "done", &item[3..]),
+        Some("[>]") => ("task", "migrated", &item[3..]),
+        Some("[-]") => ("task", "cancelled", &item[3..]),
+        _ => ("note", "open", item),
+    };
+    let text = text.trim();
+
+    // bjourn writes cancelled tasks struck through
+    match text.strip_prefix("~~").and_then(|t| t.strip_suffix("~~")) {
+        Some(t) if kind == "task" => (t.to_string(), kind, "cancelled"),
+        _ => (text.to_string(), kind, status),
+    }
+}
+
+// Reads * - and + list items, the day comes from ## YYYY-MM-DD headings, front matter or the
+// default from the file name, items before any day are skipped
+pub fn parse_markdown(content: &str, default_date: Option<NaiveDate>) -> Vec<db::NewBullet> {
+    let mut bullets = Vec::new();
+    let mut date = default_date;
+    let mut lines = content.lines().peekable();
+
+    // YAML front matter as written by bjourn export
+    if lines.peek().map(|l| l.trim()) == Some("---") {
+        lines.next();
+        for line in lines.by_ref() {
+            if line.trim() == "---" {
+                break;
+            }
+            if let Some(d) = line
+                .strip_prefix("date:")
+                .and_then(|d| leading_date(d.trim()))
+            {
+                date = Some(d);
+            }
+        }
+    }
+
+    for line in lines {
+        let line = line.trim();
+
+        // a heading is #s followed by a space, so a line starting with a #tag isn't one
+        let hashes = line.chars().take_while(|c| *c == '#').count();
+        if hashes > 0 && line[hashes..].starts_with(' ') {
+            if let Some(d) = leading_date(line[hashes..].trim()) {
+                date = Some(d);
+            }
+            continue;
+        }
+
+        let item = ["* ", "- ", "+ "]
+            .iter()
+            .find_map(|marker| line.strip_prefix(marker));
+        if let (Some(item), Some(d)) = (item, date) {
+            let (text, kind, status) = markdown_item(item.trim());
+            if !text.is_empty() {
+                bullets.push(new_bullet(d, &text, kind, status));
+            }
+        }
+    }
+
+    bullets
+}
+
+// The start of a jrnl entry, e.g. "2025-01-01 09:30 Title" or "[2025-01-01 09:30:00 AM] Title"
+fn jrnl_header(line: &str) -> Option<(NaiveDateTime, &str)> {
+    let (rest, bracketed) = match line.strip_prefix('[') {
+        Some(r) => (r, true),
+        None => (line, false),
+    };
+    let date = leading_date(rest)?;
+    let rest = rest.get(10..)?.strip_prefix([' ', 'T'])?;
+
+    let (time, title) = if bracketed {
+        rest.split_once(']')?
+    } else {
+        // a 12 hour time has AM or PM after it
+        let mut end = rest.find(' ').unwrap_or(rest.len());
+        let after = rest[end..].trim_start();
+        if after.starts_with("AM") || after.starts_with("PM") {
+            end = rest.len() - after.len() + 2;
+        }
+        rest.split_at(end)
+    };
+
+    let time = ["%H:%M:%S", "%H:%M", "%I:%M:%S %p", "%I:%M %p"]
+        .iter()
+        .find_map(|f| NaiveTime::parse_from_str(time.trim(), f).ok())?;
+
+    Some((date.and_time(time), title.trim()))
+}
+
+pub fn is_jrnl(content: &str) -> bool {
+    content.lines().any(|l| jrnl_header(l).is_some())
+}
+
+// Reads jrnl entries, each entry is a bullet with the body on the following lines
+pub fn parse_jrnl(content: &str) -> Vec<db::NewBullet> {
+    let mut bullets: Vec<db::NewBullet> = Vec::new();
+    let mut current: Option<(NaiveDateTime, String)> = None;
+
+    let mut finish = |entry: Option<(NaiveDateTime, String)>| {
+        if let Some((added, text)) = entry {
+            let text = text.trim();
+            if !text.is_empty() {
+                let mut bullet = new_bullet(added.date(), text, "note", "open");
+                bullet.added = Some(added.format("%Y-%m-%d %H:%M:%S").to_string());
+                bullets.push(bullet);
+            }
+        }
+    };
+
+    for line in content.lines() {
+        match jrnl_header(line) {
+            Some((added, title)) => {
+                finish(current.take());
+                // a starred entry has a * before the title
+                let title = title.strip_prefix("* ").unwrap_or(title);
+                current = Some((added, title.to_string()));
+            }
+            None => {
+                if let Some((_, ref mut text)) = current {
+                    text.push('\n');
+                    text.push_str(line.trim_end());
+                }
+            }
+        }
+    }
+    finish(current.take());
+
+    bullets
+}
+
+// bullets in the trash of a backup are skipped, restore brings those back
+fn json_bullet(value: &serde_json::Value, date: Option<&str>) -> Option<db::NewBullet> {
+    if value.get("deleted_at").is_some_and(|d| !d.is_null()) {
+        return None;
+    }
+    let text = value
+        .get("bullet")
+        .or_else(|| value.get("text"))?
+        .as_str()?;
+    let date = value.get("date").and_then(|d| d.as_str()).or(date)?;
+    // unknown kinds and statuses fall back to a note that is open
+    let string = |key: &str, allowed: &[&str]| {
+        value
+            .get(key)
+            .and_then(|v| v.as_str())
+            .filter(|v| allowed.contains(v))
+            .unwrap_or(allowed[0])
+            .to_string()
+    };
+
+    Some(db::NewBullet {
+        list_date: leading_date(date)?.format("%Y-%m-%d").to_string(),
+        text: text.to_string(),
+        kind: string("kind", &db::KINDS),
+        status: string("status", &db::STATUSES),
+        // an added time in another format is left out, the time it is imported is used
+        added: value
+            .get("added")
+            .and_then(|a| a.as_str())
+            .filter(|a| NaiveDateTime::parse_from_str(a, "%Y-%m-%d %H:%M:%S").is_ok())
+            .map(|a| a.to_string()),
+    })
+}
+
+// Reads the json written by list -o json or ndjson, either a list of bullets or of days
+pub fn parse_json(content: &str) -> Result<Vec<db::NewBullet>, String> {
+    let values: Vec<serde_json::Value> = match serde_json::from_str(content) {
+        Ok(serde_json::Value::Array(values)) => values,
+        // a single bullet or day, or a backup with a list of bullets
+        Ok(serde_json::Value::Object(o)) => vec![serde_json::Value::Object(o)],
+        Ok(_) => return Err("Expected a list of bullets".to_string()),
+        // one object per line
+        Err(e) => content
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<Vec<serde_json::Value>, _>>()
+            .map_err(|_| e.to_string())?,
+    };
+
+    let mut bullets = Vec::new();
+    for value in &values {
+        match value.get("bullets").and_then(|b| b.as_array()) {
+            Some(day) => {
+                let date = value.get("date").and_then(|d| d.as_str());
+                bullets.extend(day.iter().filter_map(|b| json_bullet(b, date)));
+            }
+            None => bullets.extend(json_bullet(value, None)),
+        }
+    }
+
+    Ok(bullets)
+}
+
+// Reads a file, the format is from the extension or the content
+pub fn parse_file(path: &Path) -> Result<Vec<db::NewBullet>, String> {
+    let content =
+        std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+    if path.extension().and_then(|e| e.to_str()) == Some("json") {
+        return parse_json(&content).map_err(|e| format!("{}: {}", path.display(), e));
+    }
+    if is_jrnl(&content) {
+        return Ok(parse_jrnl(&content));
+    }
+
+    let default_date = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .and_then(find_date);
+    Ok(parse_markdown(&content, default_date))
+}
+
+// the files to read, a directory is read in name order
+fn import_files(path: &Path) -> Result<Vec<std::path::PathBuf>, String> {
+    if !path.exists() {
+        return Err(format!("{}: no such file or directory", path.display()));
+    }
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let entries = std::fs::read_dir(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut files: Vec<std::path::PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.is_file()
+                && p.extension()
+                    .and_then(|e| e.to_str())
+                    .is_some_and(|e| EXTENSIONS.contains(&e))
+        })
+        .collect();
+    files.sort();
+
+    Ok(files)
+}
+
+pub fn import(args: &bargs::BArgs) {
+    let path = match args.input {
+        Some(ref p) => p.trim().to_string(),
+        None => {
+            eprintln!("Error: import requires a file or directory");
+            std::process::exit(exitcode::USAGE);
+        }
+    };
+    let dry_run = args.has_flag("dry-run");
+
+    let files = match import_files(Path::new(&path)) {
+        Ok(f) => f,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(exitcode::NOINPUT);
+        }
+    };
+
+    let mut bullets = Vec::new();
+    for file in files {
+        match parse_file(&file) {
+            Ok(b) => bullets.extend(b),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(exitcode::DATAERR);
+            }
+        }
+    }
+
+    let new = match db::import_bullets(&bullets, dry_run) {
+        Ok(n) => n,
+        Err(e) => {
+            eprintln!("Error importing bullets: {}", e);
+            std::process::exit(exitcode::IOERR);
+        }
+    };
+    let imported = new.iter().filter(|n| **n).count();
+    let duplicates = new.len() - imported;
+
+    if dry_run {
+        for (bullet, _) in bullets.iter().zip(&new).filter(|(_, n)| **n) {
+            println!("* {} {}: {}", bullet.list_date, bullet.kind, bullet.text);
+        }
+        println!(
+            "Would import {} bullets, {} already in the journal",
+            imported, duplicates
+        );
+    } else {
+        println!(
+            "Imported {} bullets, {} already in the journal",
+            imported, duplicates
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn summary(bullets: &[db::NewBullet]) -> Vec<(&str, &str, &str, &str)> {
+        bullets
+            .iter()
+            .map(|b| {
+                (
+                    b.list_date.as_str(),
+                    b.text.as_str(),
+                    b.kind.as_str(),
+                    b.status.as_str(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_find_date() {
+        assert_eq!(find_date("2025-01-03.md"), Some(d("2025-01-03")));
+        assert_eq!(
+            find_date("journal-2025-01-03-notes.md"),
+            Some(d("2025-01-03"))
+        );
+        assert_eq!(find_date("notes.md"), None);
+    }
+
+    #[test]
+    fn test_parse_markdown() {
+        let content = "# My notes\n\
+            * before any day\n\
+            ## 2025-01-01\n\
+            \n\
+            * woke up and had breakfast\n\
+            - [x] fix the #bjourn build\n\
+            #bjourn is not a heading\n\
+            ## 2025-01-02 Thursday\n\
+            + [ ] write docs\n\
+            * [ ] ~~go running~~\n\
+            * [>] call the bank\n";
+
+        assert_eq!(
+            summary(&parse_markdown(content, None)),
+            vec![
+                ("2025-01-01", "woke up and had breakfast", "note", "open"),
+                ("2025-01-01", "fix the #bjourn build", "task", "done"),
+                ("2025-01-02", "write docs", "task", "open"),
+                ("2025-01-02", "go running", "task", "cancelled"),
+                ("2025-01-02", "call the bank", "task", "migrated"),
+            ]
+        );
+
+        // the date from the file name or the front matter of an export
+        assert_eq!(
+            summary(&parse_markdown("* one\n", Some(d("2025-02-01")))),
+            vec![("2025-02-01", "one", "note", "open")]
+        );
+        assert_eq!(
+            summary(&parse_markdown(
+                "---\ndate: 2025-03-01\ntags: []\ncount: 1\n---\n\n* exported\n",
+                None
+            )),
+            vec![("2025-03-01", "exported", "note", "open")]
+        );
+    }
+
+    #[test]
+    fn test_parse_jrnl() {
+        let content = "2025-01-01 09:30 First entry.\n\
+            With a body line.\n\
+            \n\
+            [2025-01-02 02:15:00 PM] * Starred entry\n\
+            2025-01-03 22:05 Last one\n";
+        assert!(is_jrnl(content));
+        assert!(!is_jrnl("* a markdown list\n"));
+
+        let bullets = parse_jrnl(content);
+        assert_eq!(
+            summary(&bullets),
+            vec![
+                (
+                    "2025-01-01",
+                    "First entry.\nWith a body line.",
+                    "note",
+                    "open"
+                ),
+                ("2025-01-02", "Starred entry", "note", "open"),
+                ("2025-01-03", "Last one", "note", "open"),
+            ]
+        );
+        assert_eq!(bullets[1].added.as_deref(), Some("2025-01-02 14:15:00"));
+    }
+
+    #[test]
+    fn test_parse_json() {
+        let flat = r#"[{"added":"2025-01-01 08:12:45","bullet":"breakfast","date":"2025-01-01","kind":"note","quickid":"beWLHOFj","status":"open","tags":[],"updated":null}]"#;
+        let bullets = parse_json(flat).unwrap();
+        assert_eq!(
+            summary(&bullets),
+            vec![("2025-01-01", "breakfast", "note", "open")]
+        );
+        assert_eq!(bullets[0].added.as_deref(), Some("2025-01-01 08:12:45"));
+
+        let days = r#"[{"date":"2025-01-02","bullets":[{"bullet":"ship it","kind":"task","status":"done"}]}]"#;
+        assert_eq!(
+            summary(&parse_json(days).unwrap()),
+            vec![("2025-01-02", "ship it", "task", "done")]
+        );
+
+        let ndjson = "{\"bullet\":\"one\",\"date\":\"2025-01-01\"}\n{\"bullet\":\"two\",\"date\":\"2025-01-02\"}\n";
+        assert_eq!(parse_json(ndjson).unwrap().len(), 2);
+
+        assert!(parse_json("not json").is_err());
+    }
+
+    #[test]
+    fn test_parse_json_backup() {
+        // the trash is left out and an added time that isn't a timestamp is dropped
+        let backup = r#"{"schema_version": 9, "bullets": [
+            {"quickid":"beWLHOFj","bullet":"kept","date":"2025-01-01","added":"2025-01-01 08:12:45","deleted_at":null},
+            {"quickid":"Xk2pQ9aa","bullet":"trashed","date":"2025-01-01","added":"2025-01-01 09:00:00","deleted_at":"2025-01-02 10:00:00"},
+            {"quickid":"m0Pq7zZz","bullet":"odd time","date":"2025-01-03","added":"yesterday at 9"}
+        ]}"#;
+        let bullets = parse_json(backup).unwrap();
+        assert_eq!(
+            summary(&bullets),
+            vec![
+                ("2025-01-01", "kept", "note", "open"),
+                ("2025-01-03", "odd time", "note", "open")
+            ]
+        );
+        assert_eq!(bullets[0].added.as_deref(), Some("2025-01-01 08:12:45"));
+        assert_eq!(bullets[1].added, None);
+    }
+}
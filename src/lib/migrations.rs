@@ -0,0 +1,491 @@
+// Numbered schema migrations for the SQLite database
+use nanoid::nanoid;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::fmt;
+
+type Migration = (&'static str, fn(&Connection) -> rusqlite::Result<()>);
+
+// each migration moves the schema up one version, the version is its position in the list
+// never edit or reorder a migration once released, add a new one to the end
+// migrations only call code in this file, so a change elsewhere can't change an old migration
+static MIGRATIONS: [Migration; 9] = [
+    ("create the env and bullet tables", create_tables),
+    ("unique env keys", unique_env_keys),
+    ("track when bullets are edited", add_updated),
+    ("log changes for undo", create_oplog),
+    ("soft delete bullets", add_deleted_at),
+    ("full text search index", create_fts),
+    ("tags on bullets", create_tags),
+    ("bullet kinds and task status", add_kind_status),
+    ("unique quickids", unique_quickids),
+];
+
+pub fn latest_version() -> i64 {
+    MIGRATIONS.len() as i64
+}
+
+#[derive(Debug)]
+pub enum MigrationError {
+    Sqlite(rusqlite::Error),
+    TooNew {
+        found: i64,
+        supported: i64,
+    },
+    Failed {
+        version: i64,
+        name: &'static str,
+        error: rusqlite::Error,
+    },
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MigrationError::Sqlite(e) => write!(f, "{}", e),
+            MigrationError::TooNew { found, supported } => write!(
+                f,
+                "the database schema version {} is newer than this version of bjourn supports ({}), please upgrade bjourn",
+                found, supported
+            ),
+            MigrationError::Failed { version, name, error } => {
+                write!(f, "migration {} ({}) failed: {}", version, name, error)
+            }
+        }
+    }
+}
+
+impl From<rusqlite::Error> for MigrationError {
+    fn from(e: rusqlite::Error) -> Self {
+        MigrationError::Sqlite(e)
+    }
+}
+
+// The schema version recorded in the env table, 0 for a new or pre migration database
+pub fn schema_version(conn: &Connection) -> rusqlite::Result<i64> {
+    let env_exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'env')",
+        [],
+        |row| row.get(0),
+    )?;
+    if !env_exists {
+        return Ok(0);
+    }
+
+    let version: Option<String> = conn
+        .query_row(
+            "SELECT value FROM env WHERE key = 'schema_version' ORDER BY id DESC LIMIT 1",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    Ok(version.and_then(|v| v.parse().ok()).unwrap_or(0))
+}
+
+fn set_schema_version(conn: &Connection, version: i64) -> rusqlite::Result<()> {
+    let changed = conn.execute(
+        "UPDATE env SET value = ?1 WHERE key = 'schema_version'",
+        params![version.to_string()],
+    )?;
+    if changed == 0 {
+        conn.execute(
+            "INSERT INTO env (key, value) VALUES ('schema_version', ?1)",
+            params![version.to_string()],
+        )?;
+    }
+
+    Ok(())
+}
+
+// Applies any migrations newer than the recorded version, each in its own transaction
+pub fn migrate(conn: &mut Connection) -> Result<i64, MigrationError> {
+    let current = schema_version(conn)?;
+    let supported = latest_version();
+    if current > supported {
+        return Err(MigrationError::TooNew {
+            found: current,
+            supported,
+        });
+    }
+
+    for (i, (name, migration)) in MIGRATIONS.iter().enumerate().skip(current as usize) {
+        let version = i as i64 + 1;
+        let failed = |error| MigrationError::Failed {
+            version,
+            name,
+            error,
+        };
+
+        let tx = conn.transaction()?;
+        migration(&tx).map_err(failed)?;
+        set_schema_version(&tx, version).map_err(failed)?;
+        tx.commit()?;
+    }
+
+    Ok(supported)
+}
+
+fn add_column_if_missing(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    def: &str,
+) -> rusqlite::Result<()> {
+    let exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM pragma_table_info(?1) WHERE name = ?2)",
+        params![table, column],
+        |row| row.get(0),
+    )?;
+
+    if !exists {
+        conn.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, def),
+            [],
+        )?;
+    }
+
+    Ok(())
+}
+
+// 1: the schema as of 0.2.x, databases from then already have these tables
+fn create_tables(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS env (
+          id INTEGER PRIMARY KEY AUTOINCREMENT,
+          key TEXT NOT NULL,
+          value TEXT NOT NULL
+      );
+      CREATE TABLE IF NOT EXISTS bjourn (
+          id INTEGER PRIMARY KEY AUTOINCREMENT,
+          quickid TEXT NOT NULL,
+          added TEXT NOT NULL,
+          list_date TEXT NOT NULL,
+          text TEXT NOT NULL
+      );",
+    )
+}
+
+// 2: 0.2.x inserted a new version row on every run, keep the first of each key
+fn unique_env_keys(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "DELETE FROM env WHERE key <> 'schema_version'
+            AND id NOT IN (SELECT MIN(id) FROM env GROUP BY key);
+      DELETE FROM env WHERE key = 'schema_version'
+            AND id NOT IN (SELECT MAX(id) FROM env WHERE key = 'schema_version');
+      CREATE UNIQUE INDEX IF NOT EXISTS env_key ON env (key);",
+    )
+}
+
+// 3: the last time a bullet was edited
+fn add_updated(conn: &Connection) -> rusqlite::Result<()> {
+    add_column_if_missing(conn, "bjourn", "updated", "TEXT")
+}
+
+// 4: a log of changes so they can be undone
+fn create_oplog(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS oplog (
+          id INTEGER PRIMARY KEY AUTOINCREMENT,
+          op TEXT NOT NULL,
+          bullet_id INTEGER NOT NULL,
+          data TEXT,
+          created TEXT NOT NULL
+      );",
+    )
+}
+
+// 5: removed bullets go to the trash
+fn add_deleted_at(conn: &Connection) -> rusqlite::Result<()> {
+    add_column_if_missing(conn, "bjourn", "deleted_at", "TEXT")
+}
+
+// 6: full text search index, kept in sync with the bullet list by triggers
+fn create_fts(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS bjourn_fts USING fts5(
+          text,
+          content='bjourn',
+          content_rowid='id'
+      );
+      CREATE TRIGGER IF NOT EXISTS bjourn_fts_insert AFTER INSERT ON bjourn BEGIN
+          INSERT INTO bjourn_fts(rowid, text) VALUES (new.id, new.text);
+      END;
+      CREATE TRIGGER IF NOT EXISTS bjourn_fts_delete AFTER DELETE ON bjourn BEGIN
+          INSERT INTO bjourn_fts(bjourn_fts, rowid, text) VALUES ('delete', old.id, old.text);
+      END;
+      CREATE TRIGGER IF NOT EXISTS bjourn_fts_update AFTER UPDATE OF text ON bjourn BEGIN
+          INSERT INTO bjourn_fts(bjourn_fts, rowid, text) VALUES ('delete', old.id, old.text);
+          INSERT INTO bjourn_fts(rowid, text) VALUES (new.id, new.text);
+      END;
+      INSERT INTO bjourn_fts(bjourn_fts) VALUES ('rebuild');",
+    )
+}
+
+// The tag parsing as it was when migration 7 was released, kept here so a later change to
+// tags::parse_tags doesn't change what the migration does
+fn parse_tags_v7(text: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+
+    for word in text.split_whitespace() {
+        let mut chars = word.chars();
+        let sigil = match chars.next() {
+            Some(c) if c == '#' || c == '+' => c,
+            _ => continue,
+        };
+
+        let name: String = chars
+            .take_while(|c| c.is_alphanumeric() || *c == '_' || *c == '-' || *c == '/')
+            .collect();
+        let name = name.trim_end_matches(['-', '/']);
+        if !name.chars().next().is_some_and(|c| c.is_alphabetic()) {
+            continue;
+        }
+
+        let tag = format!("{}{}", sigil, name);
+        if !tags.iter().any(|t| t.eq_ignore_ascii_case(&tag)) {
+            tags.push(tag);
+        }
+    }
+
+    tags
+}
+
+// 7: normalized #tags and +contexts, filled in from the existing bullets
+fn create_tags(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS tags (
+          id INTEGER PRIMARY KEY AUTOINCREMENT,
+          name TEXT NOT NULL UNIQUE COLLATE NOCASE
+      );
+      CREATE TABLE IF NOT EXISTS bullet_tags (
+          bullet_id INTEGER NOT NULL,
+          tag_id INTEGER NOT NULL,
+          PRIMARY KEY (bullet_id, tag_id)
+      );
+      CREATE INDEX IF NOT EXISTS bullet_tags_tag ON bullet_tags (tag_id);",
+    )?;
+
+    let mut stmt = conn.prepare("SELECT id, text FROM bjourn")?;
+    let bullets = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<rusqlite::Result<Vec<(i64, String)>>>()?;
+
+    for (id, text) in bullets {
+        for tag in parse_tags_v7(&text) {
+            conn.execute(
+                "INSERT OR IGNORE INTO tags (name) VALUES (?1)",
+                params![tag],
+            )?;
+            conn.execute(
+                "INSERT OR IGNORE INTO bullet_tags (bullet_id, tag_id)
+                SELECT ?1, id FROM tags WHERE name = ?2",
+                params![id, tag],
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+// 8: notes, tasks and events, with the status of tasks
+fn add_kind_status(conn: &Connection) -> rusqlite::Result<()> {
+    add_column_if_missing(conn, "bjourn", "kind", "TEXT NOT NULL DEFAULT 'note'")?;
+    add_column_if_missing(conn, "bjourn", "status", "TEXT NOT NULL DEFAULT 'open'")
+}
+
+static QUICKID_ALPHABET_V9: [char; 62] = [
+    'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's',
+    't', 'u', 'v', 'w', 'x', 'y', 'z', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L',
+    'M', 'N', 'O', 'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z', '0', '1', '2', '3', '4',
+    '5', '6', '7', '8', '9',
+];
+
+// A quickid no bullet has yet, as they were made when migration 9 was released
+fn unused_quickid_v9(conn: &Connection) -> rusqlite::Result<String> {
+    loop {
+        let quickid = nanoid!(8, &QUICKID_ALPHABET_V9);
+        let taken: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM bjourn WHERE quickid = ?1)",
+            params![quickid],
+            |row| row.get(0),
+        )?;
+        if !taken {
+            return Ok(quickid);
+        }
+    }
+}
+
+// 9: a quickid refers to one bullet, later bullets sharing one get a new quickid
+fn unique_quickids(conn: &Connection) -> rusqlite::Result<()> {
+    let mut stmt = conn.prepare(
+        "SELECT id FROM bjourn
+        WHERE id NOT IN (SELECT MIN(id) FROM bjourn GROUP BY quickid)
+        ORDER BY id",
+    )?;
+    let duplicates = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<i64>>>()?;
+
+    for id in duplicates {
+        conn.execute(
+            "UPDATE bjourn SET quickid = ?1 WHERE id = ?2",
+            params![unused_quickid_v9(conn)?, id],
+        )?;
+    }
+
+    conn.execute_batch("CREATE UNIQUE INDEX IF NOT EXISTS bjourn_quickid ON bjourn (quickid);")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a database as created by bjourn 0.2.x after being run a few times
+    static FIXTURE_0_2: &str = include_str!("../../tests/fixtures/bjourn-0.2.x.sql");
+
+    fn count(conn: &Connection, sql: &str) -> i64 {
+        conn.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn test_migrate_new_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), 0);
+
+        assert_eq!(migrate(&mut conn).unwrap(), latest_version());
+        assert_eq!(schema_version(&conn).unwrap(), latest_version());
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM bjourn"), 0);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM oplog"), 0);
+    }
+
+    #[test]
+    fn test_migrate_from_0_2() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(FIXTURE_0_2).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), 0);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM env"), 3);
+
+        migrate(&mut conn).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), latest_version());
+
+        // the duplicated version rows are gone and keys are now unique
+        assert_eq!(
+            count(&conn, "SELECT COUNT(*) FROM env WHERE key = 'version'"),
+            1
+        );
+        assert!(conn
+            .execute(
+                "INSERT INTO env (key, value) VALUES ('version', '0.1.0')",
+                []
+            )
+            .is_err());
+
+        // the bullets are kept, with the new columns empty
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM bjourn"), 3);
+        assert_eq!(
+            count(
+                &conn,
+                "SELECT COUNT(*) FROM bjourn WHERE updated IS NULL AND deleted_at IS NULL"
+            ),
+            3
+        );
+
+        // existing bullets become open notes
+        assert_eq!(
+            count(
+                &conn,
+                "SELECT COUNT(*) FROM bjourn WHERE kind = 'note' AND status = 'open'"
+            ),
+            3
+        );
+
+        // tags in existing bullets are picked up
+        assert_eq!(
+            count(
+                &conn,
+                "SELECT COUNT(*) FROM bullet_tags bt JOIN tags t ON t.id = bt.tag_id
+                WHERE t.name = '#bjourn'"
+            ),
+            1
+        );
+
+        // existing bullets are searchable
+        assert_eq!(
+            count(
+                &conn,
+                "SELECT COUNT(*) FROM bjourn_fts WHERE bjourn_fts MATCH 'breakfast'"
+            ),
+            1
+        );
+    }
+
+    #[test]
+    fn test_migrate_is_repeatable() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(FIXTURE_0_2).unwrap();
+
+        migrate(&mut conn).unwrap();
+        migrate(&mut conn).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), latest_version());
+        assert_eq!(
+            count(
+                &conn,
+                "SELECT COUNT(*) FROM env WHERE key = 'schema_version'"
+            ),
+            1
+        );
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM bjourn"), 3);
+    }
+
+    #[test]
+    fn test_migrate_duplicate_quickids() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(FIXTURE_0_2).unwrap();
+        conn.execute(
+            "INSERT INTO bjourn (quickid, added, list_date, text)
+            VALUES ('beWLHOFj', '2025-01-03 10:00:00', '2025-01-03', 'same quickid')",
+            [],
+        )
+        .unwrap();
+
+        migrate(&mut conn).unwrap();
+
+        // the first bullet keeps the quickid, the later one gets a new one
+        assert_eq!(
+            count(&conn, "SELECT COUNT(DISTINCT quickid) FROM bjourn"),
+            4
+        );
+        let text: String = conn
+            .query_row(
+                "SELECT text FROM bjourn WHERE quickid = 'beWLHOFj'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(text, "woke up and had breakfast");
+        assert!(conn
+            .execute(
+                "INSERT INTO bjourn (quickid, added, list_date, text)
+                VALUES ('beWLHOFj', '2025-01-04 10:00:00', '2025-01-04', 'again')",
+                []
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_refuse_newer_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn).unwrap();
+        set_schema_version(&conn, latest_version() + 1).unwrap();
+
+        match migrate(&mut conn) {
+            Err(MigrationError::TooNew { found, supported }) => {
+                assert_eq!(found, latest_version() + 1);
+                assert_eq!(supported, latest_version());
+            }
+            other => panic!("expected TooNew, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,74 @@
+// Tags (#work) and contexts (+clientA) written inline in a bullet
+
+// Finds the #tags and +contexts in the text, in the order they appear without duplicates
+// the sigil is kept as part of the name, a tag must start with a letter so #1 or +1 are ignored
+pub fn parse_tags(text: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+
+    for word in text.split_whitespace() {
+        let mut chars = word.chars();
+        let sigil = match chars.next() {
+            Some(c) if c == '#' || c == '+' => c,
+            _ => continue,
+        };
+
+        let name: String = chars
+            .take_while(|c| c.is_alphanumeric() || *c == '_' || *c == '-' || *c == '/')
+            .collect();
+        let name = name.trim_end_matches(['-', '/']);
+        if !name.chars().next().is_some_and(|c| c.is_alphabetic()) {
+            continue;
+        }
+
+        let tag = format!("{}{}", sigil, name);
+        if !tags.iter().any(|t| t.eq_ignore_ascii_case(&tag)) {
+            tags.push(tag);
+        }
+    }
+
+    tags
+}
+
+// Normalizes a tag given on the command line, a bare name is treated as a #tag
+pub fn normalize_tag(tag: &str) -> String {
+    let tag = tag.trim();
+    if tag.starts_with('#') || tag.starts_with('+') {
+        tag.to_string()
+    } else {
+        format!("#{}", tag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tags() {
+        assert_eq!(
+            parse_tags("fixed login bug #work +clientA"),
+            vec!["#work", "+clientA"]
+        );
+        assert_eq!(parse_tags("#work: fixed it, #bug."), vec!["#work", "#bug"]);
+        assert_eq!(parse_tags("#work and #Work again"), vec!["#work"]);
+        assert_eq!(
+            parse_tags("#team/backend #q1-review"),
+            vec!["#team/backend", "#q1-review"]
+        );
+        assert!(parse_tags("no tags here").is_empty());
+    }
+
+    #[test]
+    fn test_parse_tags_ignores_non_tags() {
+        assert!(parse_tags("closed issue #123 with a +1").is_empty());
+        assert!(parse_tags("a # on its own and c++").is_empty());
+        assert!(parse_tags("email me@example.com#anchor").is_empty());
+    }
+
+    #[test]
+    fn test_normalize_tag() {
+        assert_eq!(normalize_tag("work"), "#work");
+        assert_eq!(normalize_tag("#work"), "#work");
+        assert_eq!(normalize_tag("+clientA"), "+clientA");
+    }
+}
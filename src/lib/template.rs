@@ -0,0 +1,490 @@
+// Template engine for --format strings, parsed once and rendered for each bullet
+// e.g. "{added:%H:%M} {text:40}{?tags} ({tags|join(", ")}){/tags}"
+use crate::db;
+use crate::formatters;
+use chrono::format::{Item, StrftimeItems};
+use std::fmt::Write;
+
+// the placeholders that can be used, with a description for the help
+pub static FIELDS: [(&str, &str); 16] = [
+    ("quickid", "the quickid of the bullet"),
+    ("bullet", "the text of the bullet"),
+    ("text", "the text of the bullet"),
+    ("date", "the date of the bullet - ISO 8601"),
+    ("tags", "the #tags and +contexts of the bullet"),
+    ("kind", "note, task or event"),
+    ("status", "open, done, cancelled or migrated"),
+    ("signifier", "the bullet journal signifier"),
+    ("added", "the date & time the bullet was added - ISO 8601"),
+    ("updated", "the date & time the bullet was last edited"),
+    ("yyyy", "the year added"),
+    ("mm", "the month added"),
+    ("dd", "the day added"),
+    ("HH", "the hour added"),
+    ("MM", "the minute added"),
+    ("SS", "the second added"),
+];
+
+// templates that are always available with -f @name, a saved template of the same name is used instead
+pub static BUILTIN_TEMPLATES: [(&str, &str); 3] = [
+    ("standup", "- {text}{?tags} ({tags|join(\", \")}){/tags}"),
+    ("timesheet", "{date} {added:%H:%M}  {kind:5} {text}"),
+    ("slack", "• *{added:%H:%M}* {text}"),
+];
+
+// fields that take a date format e.g. {added:%H:%M}
+static DATE_FIELDS: [&str; 3] = ["date", "added", "updated"];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Filter {
+    Join(String),
+    Upper,
+    Lower,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Text(String),
+    Field {
+        name: String,
+        date_format: Option<String>,
+        width: Option<usize>,
+        filters: Vec<Filter>,
+    },
+    // the body is only shown when the field is not empty
+    If {
+        name: String,
+        body: Vec<Node>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+enum Value {
+    Text(String),
+    List(Vec<String>),
+}
+
+impl Value {
+    fn is_empty(&self) -> bool {
+        match self {
+            Value::Text(t) => t.is_empty(),
+            Value::List(l) => l.is_empty(),
+        }
+    }
+
+    fn into_string(self) -> String {
+        match self {
+            Value::Text(t) => t,
+            Value::List(l) => l.join(" "),
+        }
+    }
+}
+
+impl Template {
+    pub fn parse(template: &str) -> Result<Template, String> {
+        // the open conditionals, with the nodes before each one
+        let mut stack: Vec<(String, Vec<Node>)> = Vec::new();
+        let mut nodes: Vec<Node> = Vec::new();
+        let mut text = String::new();
+        let mut chars = template.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '}' => return Err("Unmatched } in format, use }} for a literal }".to_string()),
+                '{' => {
+                    let tag = read_tag(&mut chars)?;
+                    if !text.is_empty() {
+                        nodes.push(Node::Text(std::mem::take(&mut text)));
+                    }
+
+                    if let Some(name) = tag.strip_prefix('?') {
+                        check_field(name)?;
+                        stack.push((name.to_string(), std::mem::take(&mut nodes)));
+                    } else if let Some(name) = tag.strip_prefix('/') {
+                        match stack.pop() {
+                            Some((open, before)) if open == name => {
+                                let body = std::mem::replace(&mut nodes, before);
+                                nodes.push(Node::If { name: open, body });
+                            }
+                            Some((open, _)) => {
+                                return Err(format!("{{/{}}} in format closes {{?{}}}", name, open))
+                            }
+                            None => return Err(format!("{{/{}}} in format was not opened", name)),
+                        }
+                    } else {
+                        nodes.push(parse_field(&tag)?);
+                    }
+                }
+                _ => text.push(c),
+            }
+        }
+
+        if let Some((open, _)) = stack.pop() {
+            return Err(format!("{{?{}}} in format is missing {{/{}}}", open, open));
+        }
+        if !text.is_empty() {
+            nodes.push(Node::Text(text));
+        }
+
+        Ok(Template { nodes })
+    }
+
+    pub fn render(&self, bullet: &db::BItem) -> String {
+        let mut out = String::new();
+        render_nodes(&self.nodes, bullet, &mut out);
+
+        out
+    }
+}
+
+// Resolves -f @name to the saved or built in template, other formats are returned as they are
+pub fn resolve(format: &str) -> Result<String, String> {
+    let name = match format.strip_prefix('@') {
+        Some(n) => n,
+        None => return Ok(format.to_string()),
+    };
+
+    match db::get_template(name) {
+        Ok(Some(t)) => return Ok(t),
+        Ok(None) => {}
+        Err(e) => return Err(format!("Error reading template {}: {}", name, e)),
+    }
+
+    match BUILTIN_TEMPLATES.iter().find(|(n, _)| *n == name) {
+        Some((_, t)) => Ok(t.to_string()),
+        None => Err(format!(
+            "No template called {}, see bjourn template list",
+            name
+        )),
+    }
+}
+
+// template names are letters, numbers, - and _
+pub fn check_name(name: &str) -> Result<(), String> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "Invalid template name '{}', use letters, numbers, - and _",
+            name
+        ));
+    }
+
+    Ok(())
+}
+
+// reads up to the closing }, braces inside quotes are part of the tag
+fn read_tag(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<String, String> {
+    let mut tag = String::new();
+    let mut quoted = false;
+
+    for c in chars.by_ref() {
+        match c {
+            '"' => quoted = !quoted,
+            '}' if !quoted => return Ok(tag.trim().to_string()),
+            _ => {}
+        }
+        tag.push(c);
+    }
+
+    Err(format!(
+        "Unclosed {{{} in format, use {{{{ for a literal {{",
+        tag
+    ))
+}
+
+fn check_field(name: &str) -> Result<(), String> {
+    if !FIELDS.iter().any(|(f, _)| *f == name) {
+        let fields: Vec<&str> = FIELDS.iter().map(|(f, _)| *f).collect();
+        return Err(format!(
+            "Unknown placeholder {{{}}} in format, use one of {}",
+            name,
+            fields.join(", ")
+        ));
+    }
+
+    Ok(())
+}
+
+// splits on | outside of quotes
+fn split_filters(tag: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut quoted = false;
+    let mut start = 0;
+    for (i, c) in tag.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '|' if !quoted => {
+                parts.push(&tag[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&tag[start..]);
+
+    parts
+}
+
+// a field with an optional spec and filters e.g. tags|join(", ") or text:40
+fn parse_field(tag: &str) -> Result<Node, String> {
+    let mut parts = split_filters(tag).into_iter();
+    let field = parts.next().unwrap_or_default();
+    let (name, spec) = match field.split_once(':') {
+        Some((name, spec)) => (name.trim(), Some(spec)),
+        None => (field.trim(), None),
+    };
+    check_field(name)?;
+
+    let mut date_format = None;
+    let mut width = None;
+    if let Some(spec) = spec {
+        if DATE_FIELDS.contains(&name) && spec.contains('%') {
+            // the values have no time zone, so %Z and %z can't be rendered any more than %Q
+            let sample = chrono::NaiveDate::from_ymd_opt(2025, 1, 1)
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+                .unwrap_or_default();
+            let mut rendered = String::new();
+            if StrftimeItems::new(spec).any(|i| i == Item::Error)
+                || write!(rendered, "{}", sample.format(spec)).is_err()
+            {
+                return Err(format!("Invalid date format {} for {{{}}}", spec, name));
+            }
+            date_format = Some(spec.to_string());
+        } else {
+            width = Some(spec.trim().parse::<usize>().map_err(|_| {
+                format!(
+                    "Invalid width {} for {{{}}}, use a number e.g. {{{}:20}}",
+                    spec, name, name
+                )
+            })?);
+        }
+    }
+
+    let mut filters = Vec::new();
+    for filter in parts {
+        let filter = filter.trim();
+        let parsed = match filter {
+            "upper" => Filter::Upper,
+            "lower" => Filter::Lower,
+            _ => match filter
+                .strip_prefix("join(")
+                .and_then(|f| f.strip_suffix(')'))
+                .map(|f| f.trim())
+                .and_then(|f| f.strip_prefix('"'))
+                .and_then(|f| f.strip_suffix('"'))
+            {
+                Some(separator) if name == "tags" => Filter::Join(separator.to_string()),
+                Some(_) => {
+                    return Err(format!(
+                        "join can only be used with {{tags}}, not {{{}}}",
+                        name
+                    ))
+                }
+                None => {
+                    return Err(format!(
+                        "Unknown filter {} for {{{}}}, use join(\", \"), upper or lower",
+                        filter, name
+                    ))
+                }
+            },
+        };
+        filters.push(parsed);
+    }
+
+    Ok(Node::Field {
+        name: name.to_string(),
+        date_format,
+        width,
+        filters,
+    })
+}
+
+fn field_value(name: &str, date_format: Option<&str>, bullet: &db::BItem) -> Value {
+    let added = chrono::NaiveDateTime::parse_from_str(&bullet.added, "%Y-%m-%d %H:%M:%S").ok();
+    let added_part = |f: &str| added.map(|a| a.format(f).to_string()).unwrap_or_default();
+    let date_time = |value: &str, default: &str| {
+        let f = date_format.unwrap_or(default);
+        match chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S") {
+            Ok(d) => d.format(f).to_string(),
+            Err(_) => match chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+                // a date has no time, so a time format would fail to render
+                Ok(d) => d.and_hms_opt(0, 0, 0).unwrap().format(f).to_string(),
+                Err(_) => value.to_string(),
+            },
+        }
+    };
+
+    let value = match name {
+        "quickid" => bullet.quickid.clone(),
+        "bullet" | "text" => bullet.text.clone(),
+        "date" => date_time(&bullet.list_date, "%Y-%m-%d"),
+        "tags" => return Value::List(bullet.tags.clone()),
+        "kind" => bullet.kind.clone(),
+        "status" => bullet.status.clone(),
+        "signifier" => formatters::signifier(bullet).to_string(),
+        "added" => date_time(&bullet.added, "%Y-%m-%d %H:%M:%S"),
+        "updated" => match bullet.updated {
+            Some(ref u) => date_time(u, "%Y-%m-%d %H:%M:%S"),
+            None => String::new(),
+        },
+        "yyyy" => added_part("%Y"),
+        "mm" => added_part("%m"),
+        "dd" => added_part("%d"),
+        "HH" => added_part("%H"),
+        "MM" => added_part("%M"),
+        "SS" => added_part("%S"),
+        _ => String::new(),
+    };
+
+    Value::Text(value)
+}
+
+// pads to the width, longer values are cut short with an ellipsis
+fn fit_width(value: String, width: usize) -> String {
+    let len = value.chars().count();
+    if len <= width {
+        return format!("{}{}", value, " ".repeat(width - len));
+    }
+    if width == 0 {
+        return String::new();
+    }
+
+    let mut cut: String = value.chars().take(width - 1).collect();
+    cut.push('…');
+    cut
+}
+
+fn render_nodes(nodes: &[Node], bullet: &db::BItem, out: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(t) => out.push_str(t),
+            Node::Field {
+                name,
+                date_format,
+                width,
+                filters,
+            } => {
+                let mut value = field_value(name, date_format.as_deref(), bullet);
+                for filter in filters {
+                    value = match (filter, value) {
+                        (Filter::Join(sep), Value::List(l)) => Value::Text(l.join(sep)),
+                        (Filter::Upper, v) => Value::Text(v.into_string().to_uppercase()),
+                        (Filter::Lower, v) => Value::Text(v.into_string().to_lowercase()),
+                        (_, v) => v,
+                    };
+                }
+
+                let value = value.into_string();
+                match width {
+                    Some(w) => out.push_str(&fit_width(value, *w)),
+                    None => out.push_str(&value),
+                }
+            }
+            Node::If { name, body } => {
+                if !field_value(name, None, bullet).is_empty() {
+                    render_nodes(body, bullet, out);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bullet() -> db::BItem {
+        db::BItem {
+            id: 1,
+            quickid: "beWLHOFj".to_string(),
+            added: "2025-01-01 08:12:45".to_string(),
+            list_date: "2025-01-01".to_string(),
+            text: "Had breakfast with #family +home".to_string(),
+            updated: None,
+            deleted_at: None,
+            kind: "note".to_string(),
+            status: "open".to_string(),
+            tags: vec!["#family".to_string(), "+home".to_string()],
+        }
+    }
+
+    fn render(template: &str) -> String {
+        Template::parse(template).unwrap().render(&bullet())
+    }
+
+    #[test]
+    fn test_fields() {
+        assert_eq!(render("* {bullet}"), "* Had breakfast with #family +home");
+        assert_eq!(render("{added}"), "2025-01-01 08:12:45");
+        assert_eq!(render("{date} {quickid}"), "2025-01-01 beWLHOFj");
+        assert_eq!(render("{HH}:{MM}"), "08:12");
+        assert_eq!(render("{updated}"), "");
+    }
+
+    #[test]
+    fn test_specs_and_filters() {
+        assert_eq!(render("{added:%H:%M}"), "08:12");
+        assert_eq!(render("{date:%d/%m/%Y}"), "01/01/2025");
+        assert_eq!(render("[{kind:6}]"), "[note  ]");
+        assert_eq!(render("{text:8}"), "Had bre…");
+        assert_eq!(render("{tags}"), "#family +home");
+        assert_eq!(render("{tags|join(\", \")}"), "#family, +home");
+        assert_eq!(render("{status|upper}"), "OPEN");
+    }
+
+    #[test]
+    fn test_conditionals_and_escapes() {
+        assert_eq!(render("{?tags}[{tags}]{/tags}"), "[#family +home]");
+        assert_eq!(render("x{?updated} edited {updated}{/updated}"), "x");
+        assert_eq!(render("{{bullet}}"), "{bullet}");
+        assert_eq!(render("{{{kind}}}"), "{note}");
+    }
+
+    #[test]
+    fn test_builtin_templates() {
+        for (name, format) in BUILTIN_TEMPLATES.iter() {
+            assert!(Template::parse(format).is_ok(), "{} does not parse", name);
+        }
+        assert_eq!(
+            Template::parse(BUILTIN_TEMPLATES[0].1)
+                .unwrap()
+                .render(&bullet()),
+            "- Had breakfast with #family +home (#family, +home)"
+        );
+        assert_eq!(resolve("{HH}").unwrap(), "{HH}");
+        assert!(check_name("stand-up_2").is_ok());
+        assert!(check_name("stand up").is_err());
+        assert!(check_name("").is_err());
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(Template::parse("{nope}").unwrap_err().contains("nope"));
+        assert!(Template::parse("{bullet").is_err());
+        assert!(Template::parse("bullet}").is_err());
+        assert!(Template::parse("{?tags}x").is_err());
+        assert!(Template::parse("{?tags}x{/kind}").is_err());
+        assert!(Template::parse("{text:wide}").is_err());
+        assert!(Template::parse("{text|join(\",\")}").is_err());
+        assert!(Template::parse("{tags|shout}").is_err());
+        assert!(Template::parse("{added:%Q}").is_err());
+        assert!(Template::parse("{added:%Z}").is_err());
+        assert!(Template::parse("{updated:%H:%M %z}").is_err());
+        assert!(Template::parse("{date:%:z}").is_err());
+    }
+}
@@ -0,0 +1,424 @@
+// Full screen mode for browsing and editing the journal, built with the tui feature
+use crate::dates;
+use crate::db;
+use crate::formatters;
+use ratatui::backend::Backend;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::style::{Modifier, Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::{Frame, Terminal};
+use rusqlite::Connection;
+
+static HELP: &str =
+    "q quit  tab switch  j/k move  a add  t add task  e edit  d delete  x toggle done";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Focus {
+    Days,
+    Bullets,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mode {
+    Normal,
+    // typing the text of a new bullet of the kind
+    Add(String),
+    // typing the new text of the bullet with the quickid
+    Edit(String),
+}
+
+pub struct App {
+    conn: Connection,
+    // the days with entries and today, newest first
+    pub days: Vec<(String, i64)>,
+    pub day: usize,
+    pub bullets: Vec<db::BItem>,
+    pub bullet: usize,
+    pub focus: Focus,
+    pub mode: Mode,
+    pub input: String,
+    pub message: Option<String>,
+}
+
+impl App {
+    pub fn new(conn: Connection) -> rusqlite::Result<App> {
+        let mut app = App {
+            conn,
+            days: Vec::new(),
+            day: 0,
+            bullets: Vec::new(),
+            bullet: 0,
+            focus: Focus::Bullets,
+            mode: Mode::Normal,
+            input: String::new(),
+            message: None,
+        };
+        app.load_days(&dates::today().format("%Y-%m-%d").to_string())?;
+
+        Ok(app)
+    }
+
+    pub fn selected_day(&self) -> &str {
+        &self.days[self.day].0
+    }
+
+    fn selected_bullet(&self) -> Option<&db::BItem> {
+        self.bullets.get(self.bullet)
+    }
+
+    // reloads the days keeping the given one selected
+    fn load_days(&mut self, select: &str) -> rusqlite::Result<()> {
+        let mut days = db::count_by_date_in(&self.conn, "0001-01-01", "9999-12-31")?;
+        let today = dates::today().format("%Y-%m-%d").to_string();
+        if !days.iter().any(|(d, _)| *d == today) {
+            days.push((today, 0));
+        }
+        days.sort_by(|a, b| b.0.cmp(&a.0));
+
+        self.day = days.iter().position(|(d, _)| d == select).unwrap_or(0);
+        self.days = days;
+        self.load_bullets()
+    }
+
+    fn load_bullets(&mut self) -> rusqlite::Result<()> {
+        self.bullets = db::list_bullets_in(&self.conn, &self.days[self.day].0)?;
+        if self.bullet >= self.bullets.len() {
+            self.bullet = self.bullets.len().saturating_sub(1);
+        }
+
+        Ok(())
+    }
+
+    fn move_selection(&mut self, down: bool) -> rusqlite::Result<()> {
+        let (index, len) = match self.focus {
+            Focus::Days => (&mut self.day, self.days.len()),
+            Focus::Bullets => (&mut self.bullet, self.bullets.len()),
+        };
+        if down && *index + 1 < len {
+            *index += 1;
+        } else if !down && *index > 0 {
+            *index -= 1;
+        }
+
+        if self.focus == Focus::Days {
+            self.bullet = 0;
+            self.load_bullets()?;
+        }
+
+        Ok(())
+    }
+
+    // Handles a key press, returns true when the app should quit
+    pub fn handle_key(&mut self, key: KeyEvent) -> rusqlite::Result<bool> {
+        if self.mode != Mode::Normal {
+            match key.code {
+                KeyCode::Esc => {
+                    self.mode = Mode::Normal;
+                    self.input.clear();
+                }
+                KeyCode::Enter => self.submit()?,
+                KeyCode::Backspace => {
+                    self.input.pop();
+                }
+                KeyCode::Char(c) => self.input.push(c),
+                _ => {}
+            }
+            return Ok(false);
+        }
+
+        self.message = None;
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(true),
+            KeyCode::Tab | KeyCode::Left | KeyCode::Right | KeyCode::Char('h' | 'l') => {
+                self.focus = match self.focus {
+                    Focus::Days => Focus::Bullets,
+                    Focus::Bullets => Focus::Days,
+                }
+            }
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(true)?,
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(false)?,
+            KeyCode::Char('a') => self.mode = Mode::Add("note".to_string()),
+            KeyCode::Char('t') => self.mode = Mode::Add("task".to_string()),
+            KeyCode::Char('e') => {
+                if let Some(b) = self.selected_bullet().cloned() {
+                    self.input = b.text;
+                    self.mode = Mode::Edit(b.quickid);
+                }
+            }
+            KeyCode::Char('d') | KeyCode::Delete => self.delete()?,
+            KeyCode::Char('x') | KeyCode::Char(' ') => self.toggle_done()?,
+            _ => {}
+        }
+
+        Ok(false)
+    }
+
+    fn submit(&mut self) -> rusqlite::Result<()> {
+        let text = self.input.trim().to_string();
+        let mode = std::mem::replace(&mut self.mode, Mode::Normal);
+        self.input.clear();
+        if text.is_empty() {
+            return Ok(());
+        }
+
+        let day = self.selected_day().to_string();
+        match mode {
+            Mode::Add(kind) => {
+                db::add_bullet_in(&mut self.conn, &text, Some(&day), &kind)?;
+                self.load_days(&day)?;
+                self.bullet = self.bullets.len().saturating_sub(1);
+                self.focus = Focus::Bullets;
+            }
+            Mode::Edit(quickid) => {
+                db::edit_bullet_in(&mut self.conn, &quickid, &text)?;
+                self.load_bullets()?;
+            }
+            Mode::Normal => {}
+        }
+
+        Ok(())
+    }
+
+    fn delete(&mut self) -> rusqlite::Result<()> {
+        let quickid = match self.selected_bullet() {
+            Some(b) if self.focus == Focus::Bullets => b.quickid.clone(),
+            _ => return Ok(()),
+        };
+
+        db::remove_bullet_in(&mut self.conn, &quickid)?;
+        self.message = Some(format!("Removed {}, bjourn undo brings it back", quickid));
+        let day = self.selected_day().to_string();
+        self.load_days(&day)
+    }
+
+    fn toggle_done(&mut self) -> rusqlite::Result<()> {
+        let b = match self.selected_bullet() {
+            Some(b) => b.clone(),
+            None => return Ok(()),
+        };
+        if b.kind != "task" {
+            self.message = Some(format!(
+                "{} is a {}, only tasks can be done",
+                b.quickid, b.kind
+            ));
+            return Ok(());
+        }
+
+        let status = match b.status.as_str() {
+            "open" => "done",
+            "done" => "open",
+            other => {
+                self.message = Some(format!("{} is {}", b.quickid, other));
+                return Ok(());
+            }
+        };
+
+        db::set_status_in(&mut self.conn, &b.quickid, status)?;
+        self.load_bullets()
+    }
+}
+
+pub fn draw(frame: &mut Frame, app: &App) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(1)])
+        .split(frame.area());
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(20), Constraint::Min(20)])
+        .split(rows[0]);
+
+    let focused = |f: Focus| {
+        if app.focus == f {
+            Style::default().bold()
+        } else {
+            Style::default().dim()
+        }
+    };
+    let highlight = Style::default().add_modifier(Modifier::REVERSED);
+
+    let days: Vec<ListItem> = app
+        .days
+        .iter()
+        .map(|(day, count)| ListItem::new(format!("{} {:>3}", day, count)))
+        .collect();
+    let mut day_state = ListState::default().with_selected(Some(app.day));
+    frame.render_stateful_widget(
+        List::new(days)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Days")
+                    .border_style(focused(Focus::Days)),
+            )
+            .highlight_style(highlight),
+        columns[0],
+        &mut day_state,
+    );
+
+    let bullets: Vec<ListItem> = app
+        .bullets
+        .iter()
+        .map(|b| {
+            let style = match b.status.as_str() {
+                "cancelled" => Style::default().crossed_out().dim(),
+                "done" | "migrated" => Style::default().dim(),
+                _ => Style::default(),
+            };
+            ListItem::new(Line::styled(
+                format!("{} {}: {}", formatters::signifier(b), b.quickid, b.text),
+                style,
+            ))
+        })
+        .collect();
+    let mut bullet_state = ListState::default();
+    if app.focus == Focus::Bullets && !app.bullets.is_empty() {
+        bullet_state.select(Some(app.bullet));
+    }
+    frame.render_stateful_widget(
+        List::new(bullets)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(app.selected_day().to_string())
+                    .border_style(focused(Focus::Bullets)),
+            )
+            .highlight_style(highlight),
+        columns[1],
+        &mut bullet_state,
+    );
+
+    let status = match (&app.mode, &app.message) {
+        (Mode::Add(kind), _) => format!("add {}: {}█", kind, app.input),
+        (Mode::Edit(quickid), _) => format!("edit {}: {}█", quickid, app.input),
+        (Mode::Normal, Some(message)) => message.clone(),
+        (Mode::Normal, None) => HELP.to_string(),
+    };
+    frame.render_widget(Paragraph::new(status), rows[1]);
+}
+
+// Runs the app on the terminal until it quits
+pub fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> std::io::Result<()> {
+    loop {
+        terminal.draw(|frame| draw(frame, app))?;
+
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match app.handle_key(key) {
+                Ok(true) => return Ok(()),
+                Ok(false) => {}
+                Err(e) => app.message = Some(format!("Error: {}", e)),
+            }
+        }
+    }
+}
+
+pub fn run() {
+    let app = Connection::open(db::database_location()).and_then(App::new);
+    let mut app = match app {
+        Ok(a) => a,
+        Err(e) => {
+            eprintln!("Error reading the journal: {}", e);
+            std::process::exit(exitcode::IOERR);
+        }
+    };
+
+    let mut terminal = ratatui::init();
+    let result = run_app(&mut terminal, &mut app);
+    ratatui::restore();
+
+    if let Err(e) = result {
+        eprintln!("Error: {}", e);
+        std::process::exit(exitcode::IOERR);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+    use ratatui::crossterm::event::KeyModifiers;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn type_text(app: &mut App, text: &str) {
+        for c in text.chars() {
+            app.handle_key(key(KeyCode::Char(c))).unwrap();
+        }
+        app.handle_key(key(KeyCode::Enter)).unwrap();
+    }
+
+    fn screen(app: &App) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(80, 10)).unwrap();
+        terminal.draw(|frame| draw(frame, app)).unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let mut lines = Vec::new();
+        for y in 0..buffer.area.height {
+            let line: String = (0..buffer.area.width)
+                .map(|x| buffer[(x, y)].symbol().to_string())
+                .collect();
+            lines.push(line);
+        }
+        lines.join("\n")
+    }
+
+    #[test]
+    fn test_tui_add_edit_toggle_delete() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::migrate(&mut conn).unwrap();
+
+        let mut app = App::new(conn).unwrap();
+        assert_eq!(app.days.len(), 1);
+        assert!(app.bullets.is_empty());
+
+        app.handle_key(key(KeyCode::Char('a'))).unwrap();
+        type_text(&mut app, "wrote the tui");
+        app.handle_key(key(KeyCode::Char('t'))).unwrap();
+        type_text(&mut app, "ship it");
+        assert_eq!(app.bullets.len(), 2);
+        assert_eq!(app.bullet, 1);
+        assert_eq!(app.days[0].1, 2);
+        assert!(screen(&app).contains("ship it"));
+
+        // toggle done on the task and back again
+        app.handle_key(key(KeyCode::Char('x'))).unwrap();
+        assert_eq!(app.bullets[1].status, "done");
+        app.handle_key(key(KeyCode::Char('x'))).unwrap();
+        assert_eq!(app.bullets[1].status, "open");
+
+        // notes can't be done
+        app.handle_key(key(KeyCode::Char('k'))).unwrap();
+        app.handle_key(key(KeyCode::Char('x'))).unwrap();
+        assert!(app.message.as_ref().unwrap().contains("only tasks"));
+
+        // edit replaces the text, backspace removes the last letters
+        app.handle_key(key(KeyCode::Char('e'))).unwrap();
+        for _ in 0.."the tui".len() {
+            app.handle_key(key(KeyCode::Backspace)).unwrap();
+        }
+        type_text(&mut app, "a full screen mode");
+        assert_eq!(app.bullets[0].text, "wrote a full screen mode");
+
+        // escape cancels an add
+        app.handle_key(key(KeyCode::Char('a'))).unwrap();
+        app.handle_key(key(KeyCode::Char('z'))).unwrap();
+        app.handle_key(key(KeyCode::Esc)).unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.bullets.len(), 2);
+
+        app.handle_key(key(KeyCode::Char('d'))).unwrap();
+        assert_eq!(app.bullets.len(), 1);
+        let text = screen(&app);
+        assert!(!text.contains("full screen"));
+        assert!(text.contains("Removed"));
+
+        assert!(app.handle_key(key(KeyCode::Char('q'))).unwrap());
+    }
+}
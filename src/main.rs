@@ -3,15 +3,53 @@
 #[path = "lib/bargs.rs"]
 mod bargs;
 
+#[path = "lib/config.rs"]
+mod config;
+
+#[path = "lib/dates.rs"]
+mod dates;
+
 #[path = "lib/db.rs"]
 mod db;
 
+#[path = "lib/editor.rs"]
+mod editor;
+
+#[path = "lib/tags.rs"]
+mod tags;
+
+#[path = "lib/migrations.rs"]
+mod migrations;
+
+#[path = "lib/backup.rs"]
+mod backup;
+#[path = "lib/displaycal.rs"]
+mod displaycal;
+#[path = "lib/displayfind.rs"]
+mod displayfind;
+#[path = "lib/displayreport.rs"]
+mod displayreport;
+#[path = "lib/displaystats.rs"]
+mod displaystats;
+#[path = "lib/export.rs"]
+mod export;
+#[path = "lib/import.rs"]
+mod import;
+#[cfg(feature = "tui")]
+#[path = "lib/tui.rs"]
+mod tui;
+
 #[path = "lib/displayinfo.rs"]
 mod displayinfo;
 
 #[path = "lib/displaylist.rs"]
 mod displaylist;
+#[path = "lib/formatters.rs"]
+mod formatters;
+#[path = "lib/template.rs"]
+mod template;
 
+use bargs::BJournRunner;
 use colored::Colorize;
 use std::io::IsTerminal;
 
@@ -27,6 +65,8 @@ fn main() {
         if usage == "false" || usage == "0" {
             supress_usage = true;
         }
+    } else if config::get().usage == Some(false) {
+        supress_usage = true;
     }
 
     if std::env::var("DEBUG").is_ok() {
@@ -39,16 +79,48 @@ fn main() {
     }
 
     // read in the arguments
-    let args = bargs::parse_args();
+    let mut args = bargs::parse_args();
+
+    // the color from the config file, the output and format defaults are applied by the
+    // commands that support them, flags on the command line take precedence
+    let conf = config::get();
+    if let Some(color) = &conf.color {
+        args.flag_args
+            .entry("color".to_string())
+            .or_insert(color.to_string());
+    }
+
+    match args.flag_arg("color").as_deref() {
+        Some("always") => colored::control::set_override(true),
+        Some("never") => colored::control::set_override(false),
+        _ => {}
+    }
+
     if env_debug {
         dbg!(&args);
+        dbg!(conf);
+    }
+
+    // config get, set and list, before the database so a bad db path can be fixed
+    if args.action == bargs::BAction::Config {
+        run_config(&args);
+        std::process::exit(exitcode::OK);
     }
 
     // Create the database if needed before exercising the actions
     let dbgo = db::create_database();
     if let Err(e) = dbgo {
         eprintln!("Error creating database: {}", e);
-        std::process::exit(exitcode::CANTCREAT);
+        match e {
+            migrations::MigrationError::TooNew { .. } => std::process::exit(exitcode::CONFIG),
+            _ => std::process::exit(exitcode::CANTCREAT),
+        }
+    }
+
+    // template save, list and rm
+    if args.action == bargs::BAction::Template {
+        run_template(&args);
+        std::process::exit(exitcode::OK);
     }
 
     // version
@@ -93,16 +165,51 @@ fn main() {
             }
         };
 
+        // backdate the bullet with --on
+        let list_date = match args.flag_arg("on") {
+            Some(d) => match dates::resolve_day(&d) {
+                Ok(day) => Some(day.format("%Y-%m-%d").to_string()),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(exitcode::USAGE);
+                }
+            },
+            None => None,
+        };
+
         if env_debug {
             println!("Adding: {}", input);
         }
 
-        if let Err(e) = db::add_bullet(input) {
+        let kind = args.flag_arg("kind").unwrap_or("note".to_string());
+        if !db::KINDS.contains(&kind.as_str()) {
+            eprintln!(
+                "Error: unknown kind {}, use one of {}",
+                kind,
+                db::KINDS.join(", ")
+            );
+            std::process::exit(exitcode::USAGE);
+        }
+
+        if let Err(e) = db::add_bullet(input, list_date.as_deref(), &kind) {
             eprintln!("Error adding bullet: {}", e);
             std::process::exit(exitcode::IOERR);
         }
     }
 
+    // remove the last added bullet
+    if args.action == bargs::BAction::Remove && args.has_flag("last") {
+        match db::remove_last_bullet() {
+            Ok(Some(b)) => println!("Removed {}: {}", b.quickid, b.text),
+            Ok(None) => println!("Nothing to remove"),
+            Err(e) => {
+                eprintln!("Error removing bullet: {}", e);
+                std::process::exit(exitcode::IOERR);
+            }
+        }
+        std::process::exit(exitcode::OK);
+    }
+
     // remove
     if args.action == bargs::BAction::Remove {
         let input = match &args.input {
@@ -117,12 +224,269 @@ fn main() {
             println!("Removing: {}", input);
         }
 
-        if let Err(e) = db::remove_bullet(input) {
-            eprintln!("Error removing bullet: {}", e);
+        let input = &resolve_quickid(&args, input, false);
+        match db::remove_bullet(input) {
+            Ok(0) => {
+                eprintln!("Error: no bullet found with quickid {}", input);
+                std::process::exit(exitcode::DATAERR);
+            }
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("Error removing bullet: {}", e);
+                std::process::exit(exitcode::IOERR);
+            }
+        }
+    }
+
+    // list the bullets in the trash
+    if args.action == bargs::BAction::Trash {
+        displaylist::displaytrash(&args);
+    }
+
+    // take a bullet back out of the trash, or restore a backup file
+    if args.action == bargs::BAction::Restore {
+        let input = match &args.input {
+            Some(t) => t,
+            None => {
+                eprintln!("Error: restore requires a quickid or a backup file");
+                std::process::exit(exitcode::USAGE);
+            }
+        };
+
+        // a missing backup file is reported by restore, not looked up in the trash
+        if backup::is_backup_path(input) {
+            backup::restore(&args, input);
+            std::process::exit(exitcode::OK);
+        }
+
+        let input = &resolve_quickid(&args, input, true);
+        match db::restore_bullet(input) {
+            Ok(Some(b)) => println!("Restored {}: {}", b.quickid, b.text),
+            Ok(None) => {
+                eprintln!("Error: no bullet in the trash with quickid {}", input);
+                std::process::exit(exitcode::DATAERR);
+            }
+            Err(e) => {
+                eprintln!("Error restoring bullet: {}", e);
+                std::process::exit(exitcode::IOERR);
+            }
+        }
+    }
+
+    // permanently delete bullets in the trash
+    if args.action == bargs::BAction::Purge {
+        // the age is given like 30d or 2w, or a date to purge before
+        let before = match args.flag_arg("older-than") {
+            Some(age) => {
+                let age = match age.chars().next() {
+                    Some(c) if c.is_ascii_digit() && !age.contains('-') => format!("-{}", age),
+                    _ => age,
+                };
+                match dates::resolve_day(&age) {
+                    Ok(day) => day.format("%Y-%m-%d").to_string(),
+                    Err(e) => {
+                        eprintln!("Error: {}", e);
+                        std::process::exit(exitcode::USAGE);
+                    }
+                }
+            }
+            None => "9999-12-31".to_string(),
+        };
+
+        match db::purge_trash(&before) {
+            Ok(count) => println!("Purged {} bullets from the trash", count),
+            Err(e) => {
+                eprintln!("Error purging the trash: {}", e);
+                std::process::exit(exitcode::IOERR);
+            }
+        }
+    }
+
+    // edit, either inline or in $EDITOR when no text is given
+    if args.action == bargs::BAction::Edit {
+        let input = match &args.input {
+            Some(t) => t,
+            None => {
+                eprintln!("Error: edit requires a quickid");
+                std::process::exit(exitcode::USAGE);
+            }
+        };
+
+        let (quickid, text) = match input.split_once(char::is_whitespace) {
+            Some((q, t)) => (q.to_string(), Some(t.trim().to_string())),
+            None => (input.to_string(), None),
+        };
+
+        let quickid = resolve_quickid(&args, &quickid, false);
+        let bullet = match db::get_bullet(&quickid) {
+            Ok(Some(b)) => b,
+            Ok(None) => {
+                eprintln!("Error: no bullet found with quickid {}", quickid);
+                std::process::exit(exitcode::DATAERR);
+            }
+            Err(e) => {
+                eprintln!("Error editing bullet: {}", e);
+                std::process::exit(exitcode::IOERR);
+            }
+        };
+
+        let text = match text {
+            Some(t) => t,
+            None => match editor::edit_text(&bullet.quickid, &bullet.text) {
+                Ok(t) => t,
+                Err(e) => {
+                    eprintln!("Error opening editor: {}", e);
+                    std::process::exit(exitcode::SOFTWARE);
+                }
+            },
+        };
+
+        if text.is_empty() {
+            eprintln!("Error: the new text is empty, use remove to delete a bullet");
+            std::process::exit(exitcode::USAGE);
+        }
+
+        if env_debug {
+            println!("Editing {}: {}", quickid, text);
+        }
+
+        if text != bullet.text {
+            if let Err(e) = db::edit_bullet(&quickid, &text) {
+                eprintln!("Error editing bullet: {}", e);
+                std::process::exit(exitcode::IOERR);
+            }
+        }
+    }
+
+    // undo the last add, remove, restore or edit
+    if args.action == bargs::BAction::Undo {
+        match db::undo() {
+            Ok(Some((op, b))) => match op.as_str() {
+                "add" => println!("Removed {}: {}", b.quickid, b.text),
+                "remove" => println!("Restored {}: {}", b.quickid, b.text),
+                "restore" => println!("Moved back to the trash {}: {}", b.quickid, b.text),
+                _ => println!("Reverted {} of {}: {}", op, b.quickid, b.text),
+            },
+            Ok(None) => println!("Nothing to undo"),
+            Err(e) => {
+                eprintln!("Error undoing: {}", e);
+                std::process::exit(exitcode::IOERR);
+            }
+        }
+    }
+
+    // mark a task as done or cancelled
+    if args.action == bargs::BAction::Done || args.action == bargs::BAction::Cancel {
+        let status = match args.action {
+            bargs::BAction::Done => "done",
+            _ => "cancelled",
+        };
+
+        let input = match &args.input {
+            Some(t) => t,
+            None => {
+                eprintln!("Error: marking a task {} requires a quickid", status);
+                std::process::exit(exitcode::USAGE);
+            }
+        };
+
+        let bullet = open_task(&args, input);
+        if let Err(e) = db::set_status(&bullet.quickid, status) {
+            eprintln!("Error updating bullet: {}", e);
             std::process::exit(exitcode::IOERR);
         }
     }
 
+    // move an open task to another day, defaults to today
+    if args.action == bargs::BAction::Migrate {
+        let input = match &args.input {
+            Some(t) => t,
+            None => {
+                eprintln!("Error: migrate requires a quickid");
+                std::process::exit(exitcode::USAGE);
+            }
+        };
+
+        let (quickid, date) = match input.split_once(char::is_whitespace) {
+            Some((q, d)) => (q.to_string(), d.trim().to_string()),
+            None => (input.to_string(), "today".to_string()),
+        };
+        let date = match dates::resolve_day(&date) {
+            Ok(day) => day.format("%Y-%m-%d").to_string(),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(exitcode::USAGE);
+            }
+        };
+
+        let bullet = open_task(&args, &quickid);
+        if bullet.list_date == date {
+            eprintln!("Error: {} is already on {}", bullet.quickid, date);
+            std::process::exit(exitcode::USAGE);
+        }
+
+        match db::migrate_bullet(&bullet.quickid, &date) {
+            Ok(Some(b)) => println!("Migrated to {} as {}: {}", b.list_date, b.quickid, b.text),
+            Ok(None) => {}
+            Err(e) => {
+                eprintln!("Error migrating bullet: {}", e);
+                std::process::exit(exitcode::IOERR);
+            }
+        }
+    }
+
+    // list the tags and how often they are used
+    if args.action == bargs::BAction::Tags {
+        displaylist::displaytags(&args);
+    }
+
+    // full text search
+    if args.action == bargs::BAction::Find {
+        displayfind::displayfind(&args);
+    }
+
+    // weekly and monthly digests
+    if args.action == bargs::BAction::Report {
+        displayreport::displayreport(&args);
+    }
+
+    // totals, streaks and the busiest times
+    if args.action == bargs::BAction::Stats {
+        displaystats::displaystats(&args);
+    }
+
+    // a month grid of the days with entries
+    if args.action == bargs::BAction::Cal {
+        displaycal::displaycal(&args);
+    }
+
+    // a markdown file for each day
+    if args.action == bargs::BAction::Export {
+        export::export(&args);
+    }
+
+    // bullets from markdown, jrnl or json files
+    if args.action == bargs::BAction::Import {
+        import::import(&args);
+    }
+
+    // every row and the config as json
+    if args.action == bargs::BAction::Backup {
+        backup::backup(&args);
+    }
+
+    // the full screen mode is only in builds with the tui feature
+    if args.action == bargs::BAction::Tui {
+        #[cfg(feature = "tui")]
+        tui::run();
+
+        #[cfg(not(feature = "tui"))]
+        {
+            eprintln!("Error: bjourn was built without the tui, install it with --features tui");
+            std::process::exit(exitcode::USAGE);
+        }
+    }
+
     // handle the list action
     if args.action == bargs::BAction::List {
         displaylist::displaylist(&args);
@@ -130,3 +494,167 @@ fn main() {
 
     std::process::exit(exitcode::OK);
 }
+
+// bjourn config [get key | set key value | list]
+fn run_config(args: &bargs::BArgs) {
+    let input = args.input.clone().unwrap_or("list".to_string());
+    let parts: Vec<&str> = input.splitn(3, char::is_whitespace).collect();
+
+    let result = match parts.as_slice() {
+        ["get", key] => config::get_value(key).map(|v| match v {
+            Some(v) => println!("{}", v),
+            None => std::process::exit(exitcode::DATAERR),
+        }),
+        ["set", key, value] => config::set_value(key, value.trim()),
+        ["list"] => config::list_values().map(|values| {
+            if let Some(path) = config::config_location() {
+                println!("# {}", path.display());
+            }
+            for (key, value) in values {
+                println!("{} = {}", key, value);
+            }
+        }),
+        _ => {
+            eprintln!("Usage: bjourn config [get key | set key value | list]");
+            std::process::exit(exitcode::USAGE);
+        }
+    };
+
+    if let Err(e) = result {
+        eprintln!("Error: {}", e);
+        std::process::exit(exitcode::CONFIG);
+    }
+}
+
+fn run_template(args: &bargs::BArgs) {
+    let input = args.input.clone().unwrap_or("list".to_string());
+    let parts: Vec<&str> = input.splitn(3, char::is_whitespace).collect();
+
+    match parts.as_slice() {
+        ["save", name, format] => {
+            let format = format.trim();
+            if let Err(e) = template::check_name(name)
+                .and_then(|_| template::Template::parse(format).map(|_| ()))
+            {
+                eprintln!("Error: {}", e);
+                std::process::exit(exitcode::DATAERR);
+            }
+            if let Err(e) = db::save_template(name, format) {
+                eprintln!("Error saving template: {}", e);
+                std::process::exit(exitcode::IOERR);
+            }
+            println!("Saved template {}, use it with -f @{}", name, name);
+        }
+        ["list"] => {
+            let saved = match db::list_templates() {
+                Ok(t) => t,
+                Err(e) => {
+                    eprintln!("Error listing templates: {}", e);
+                    std::process::exit(exitcode::IOERR);
+                }
+            };
+            for (name, format) in &saved {
+                println!("@{} = {}", name, format);
+            }
+            for (name, format) in template::BUILTIN_TEMPLATES.iter() {
+                if !saved.iter().any(|(n, _)| n == name) {
+                    println!("@{} = {} (built in)", name, format);
+                }
+            }
+        }
+        ["rm" | "remove", name] => match db::remove_template(name) {
+            Ok(0) if template::BUILTIN_TEMPLATES.iter().any(|(n, _)| n == name) => {
+                eprintln!("Error: {} is a built in template", name);
+                std::process::exit(exitcode::DATAERR);
+            }
+            Ok(0) => {
+                eprintln!("Error: no template called {}", name);
+                std::process::exit(exitcode::DATAERR);
+            }
+            Ok(_) => println!("Removed template {}", name),
+            Err(e) => {
+                eprintln!("Error removing template: {}", e);
+                std::process::exit(exitcode::IOERR);
+            }
+        },
+        _ => {
+            eprintln!("Usage: bjourn template [save name format | list | rm name]");
+            std::process::exit(exitcode::USAGE);
+        }
+    }
+}
+
+// The full quickid for a quickid, the start of one, or a position like 3 or @-1 in the list
+// for the --on day, quickids in the trash are used when restoring
+fn resolve_quickid(args: &bargs::BArgs, input: &str, trash: bool) -> String {
+    if let Some(position) = bargs::parse_position(input).filter(|_| !trash) {
+        let day = match dates::resolve_day(&args.flag_arg("on").unwrap_or("today".to_string())) {
+            Ok(d) => d.format("%Y-%m-%d").to_string(),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(exitcode::USAGE);
+            }
+        };
+
+        return match db::quickid_at(&day, position) {
+            Ok(Some(quickid)) => quickid,
+            Ok(None) => {
+                eprintln!("Error: there is no bullet {} on {}", input, day);
+                std::process::exit(exitcode::DATAERR);
+            }
+            Err(e) => {
+                eprintln!("Error reading bullet: {}", e);
+                std::process::exit(exitcode::IOERR);
+            }
+        };
+    }
+
+    match db::match_quickid(input, trash) {
+        Ok(db::QuickidMatch::One(quickid)) => quickid,
+        // not found is reported by the caller with the input
+        Ok(db::QuickidMatch::None) => input.to_string(),
+        Ok(db::QuickidMatch::Ambiguous(quickids)) => {
+            eprintln!(
+                "Error: {} is ambiguous, it could be {}",
+                input,
+                quickids.join(", ")
+            );
+            std::process::exit(exitcode::DATAERR);
+        }
+        Err(e) => {
+            eprintln!("Error reading bullet: {}", e);
+            std::process::exit(exitcode::IOERR);
+        }
+    }
+}
+
+// looks up a task that is still open, exiting with an error otherwise
+fn open_task(args: &bargs::BArgs, quickid: &str) -> db::BItem {
+    let quickid = &resolve_quickid(args, quickid, false);
+    let bullet = match db::get_bullet(quickid) {
+        Ok(Some(b)) => b,
+        Ok(None) => {
+            eprintln!("Error: no bullet found with quickid {}", quickid);
+            std::process::exit(exitcode::DATAERR);
+        }
+        Err(e) => {
+            eprintln!("Error reading bullet: {}", e);
+            std::process::exit(exitcode::IOERR);
+        }
+    };
+
+    if bullet.kind != "task" {
+        eprintln!(
+            "Error: {} is a {}, only tasks can change status",
+            bullet.quickid, bullet.kind
+        );
+        std::process::exit(exitcode::DATAERR);
+    }
+
+    if bullet.status != "open" {
+        eprintln!("Error: {} is already {}", bullet.quickid, bullet.status);
+        std::process::exit(exitcode::DATAERR);
+    }
+
+    bullet
+}
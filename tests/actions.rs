@@ -0,0 +1,63 @@
+// Actions given as a plain word, a mistyped input is an error and not added as a note
+use assert_cmd::Command;
+use std::path::PathBuf;
+
+fn setup(name: &str) -> (PathBuf, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("bjourn-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+
+    (dir.clone(), dir.join("bjourn.db"))
+}
+
+fn bjourn(dir: &PathBuf, db: &PathBuf, line: &str) -> Command {
+    let mut cmd = Command::cargo_bin("bjourn").unwrap();
+    cmd.env("XDG_CONFIG_HOME", dir)
+        .env("BJOURN_DB", db)
+        .args(line.split_whitespace())
+        .write_stdin("");
+    cmd
+}
+
+fn listed(dir: &PathBuf, db: &PathBuf) -> String {
+    let out = bjourn(dir, db, "list -o json").assert().success();
+    String::from_utf8(out.get_output().stdout.clone()).unwrap()
+}
+
+#[test]
+fn test_mistyped_action_input_is_an_error() {
+    let (dir, db) = setup("actions-typos");
+
+    for (line, code) in [
+        ("list yesterdy", exitcode::USAGE),
+        ("list garbage", exitcode::USAGE),
+        ("cal 2025-13", exitcode::USAGE),
+        ("report lastweek", exitcode::USAGE),
+        ("find", exitcode::USAGE),
+        ("remove abc", exitcode::DATAERR),
+        ("import missing.md", exitcode::NOINPUT),
+    ] {
+        bjourn(&dir, &db, line).assert().failure().code(code);
+    }
+
+    // none of them were added
+    assert!(!listed(&dir, &db).contains("\"bullet\""));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_prose_starting_with_an_action_is_added() {
+    let (dir, db) = setup("actions-prose");
+
+    bjourn(&dir, &db, "done with the quarterly report")
+        .assert()
+        .success();
+    bjourn(&dir, &db, "stats with friends").assert().success();
+
+    let out = listed(&dir, &db);
+    assert!(out.contains("done with the quarterly report"));
+    assert!(out.contains("stats with friends"));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,56 @@
+// The config file defaults, run against the built binary with its own database and config
+use assert_cmd::Command;
+use std::path::PathBuf;
+
+fn setup(name: &str, config: &str) -> (PathBuf, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("bjourn-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("bjourn")).unwrap();
+    std::fs::write(dir.join("bjourn").join("config.toml"), config).unwrap();
+
+    (dir.clone(), dir.join("bjourn.db"))
+}
+
+fn bjourn(dir: &PathBuf, db: &PathBuf, args: &[&str]) -> Command {
+    let mut cmd = Command::cargo_bin("bjourn").unwrap();
+    cmd.env("XDG_CONFIG_HOME", dir)
+        .env("BJOURN_DB", db)
+        .args(args)
+        .write_stdin("");
+    cmd
+}
+
+#[test]
+fn test_configured_output_only_for_commands_that_support_it() {
+    let (dir, db) = setup("config-output", "output = \"csv\"\n");
+
+    bjourn(&dir, &db, &["add", "breakfast with the #family"])
+        .assert()
+        .success();
+
+    // lists use the configured csv
+    let listed = bjourn(&dir, &db, &["list"]).assert().success();
+    let out = String::from_utf8(listed.get_output().stdout.clone()).unwrap();
+    assert!(out.contains("breakfast with the #family"));
+    assert_eq!(out.lines().count(), 2, "a csv header and a row: {}", out);
+
+    // the commands without csv fall back to markdown
+    for args in [
+        vec!["tags"],
+        vec!["trash"],
+        vec!["find", "breakfast"],
+        vec!["stats"],
+        vec!["cal"],
+        vec!["report"],
+    ] {
+        bjourn(&dir, &db, &args).assert().success();
+    }
+
+    // asking for csv on the command line is still an error
+    bjourn(&dir, &db, &["tags", "-o", "csv"])
+        .assert()
+        .failure()
+        .code(exitcode::USAGE);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,23 @@
+-- A database as created by bjourn 0.2.x, the version row is inserted on every run
+CREATE TABLE env (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL
+);
+INSERT INTO env (key, value) VALUES ('version', '0.1.0');
+INSERT INTO env (key, value) VALUES ('version', '0.1.0');
+INSERT INTO env (key, value) VALUES ('version', '0.1.0');
+
+CREATE TABLE bjourn (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    quickid TEXT NOT NULL,
+    added TEXT NOT NULL,
+    list_date TEXT NOT NULL,
+    text TEXT NOT NULL
+);
+INSERT INTO bjourn (quickid, added, list_date, text)
+    VALUES ('beWLHOFj', '2025-01-01 08:12:45', '2025-01-01', 'woke up and had breakfast');
+INSERT INTO bjourn (quickid, added, list_date, text)
+    VALUES ('ZScG1V3i', '2025-01-01 13:02:11', '2025-01-01', 'added version 0.2.1 of #bjourn');
+INSERT INTO bjourn (quickid, added, list_date, text)
+    VALUES ('k9TQ2mXa', '2025-01-02 09:30:00', '2025-01-02', 'planning for the week');